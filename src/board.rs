@@ -26,18 +26,23 @@ mod fen;
 mod gamestate;
 mod history;
 mod playmove;
+mod polyglot;
+mod san;
 mod utils;
 mod zobrist;
 
 use self::{
-    defs::{Pieces, BB_SQUARES},
+    defs::{Pieces, Sq, BB_SQUARES},
     gamestate::GameState,
     history::History,
     zobrist::{ZobristKey, ZobristRandoms},
 };
 use crate::{
     defs::{Bitboard, NrOf, Piece, Side, Sides, Square, EMPTY},
-    evaluation::psqt::{self, FLIP, PSQT_MG},
+    evaluation::{
+        defs::{MAX_PHASE, PHASE_WEIGHTS},
+        psqt::{self, PSQT_MG},
+    },
     misc::bits,
 };
 use std::sync::Arc;
@@ -99,12 +104,20 @@ impl Board {
         self.bb_side[side] ^= BB_SQUARES[square];
         self.piece_list[square] = Pieces::NONE;
         self.game_state.zobrist_key ^= self.zr.piece(side, piece, square);
+        if piece == Pieces::PAWN {
+            self.game_state.pawn_key ^= self.zr.piece(side, piece, square);
+        }
 
         // Incremental updates
         // =============================================================
         let flip = side == Sides::WHITE;
-        let s = if flip { FLIP[square] } else { square };
+        let s = if flip {
+            Sq::new(square).flip().index()
+        } else {
+            square
+        };
         self.game_state.psqt[side] -= PSQT_MG[piece][s];
+        self.game_state.phase -= PHASE_WEIGHTS[piece];
     }
 
     // Put a piece onto the board, for the given side, piece, and square.
@@ -113,12 +126,20 @@ impl Board {
         self.bb_side[side] |= BB_SQUARES[square];
         self.piece_list[square] = piece;
         self.game_state.zobrist_key ^= self.zr.piece(side, piece, square);
+        if piece == Pieces::PAWN {
+            self.game_state.pawn_key ^= self.zr.piece(side, piece, square);
+        }
 
         // Incremental updates
         // =============================================================
         let flip = side == Sides::WHITE;
-        let s = if flip { FLIP[square] } else { square };
+        let s = if flip {
+            Sq::new(square).flip().index()
+        } else {
+            square
+        };
         self.game_state.psqt[side] += PSQT_MG[piece][s];
+        self.game_state.phase += PHASE_WEIGHTS[piece];
     }
 
     // Remove a piece from the from-square, and put it onto the to-square.
@@ -130,7 +151,7 @@ impl Board {
     // Set a square as being the current ep-square.
     pub fn set_ep_square(&mut self, square: Square) {
         self.game_state.zobrist_key ^= self.zr.en_passant(self.game_state.en_passant);
-        self.game_state.en_passant = Some(square as u8);
+        self.game_state.en_passant = Some(Sq::new(square));
         self.game_state.zobrist_key ^= self.zr.en_passant(self.game_state.en_passant);
     }
 
@@ -154,6 +175,28 @@ impl Board {
         self.game_state.castling = new_permissions;
         self.game_state.zobrist_key ^= self.zr.castling(self.game_state.castling);
     }
+
+    // Returns the Zobrist key of the position, as it is kept up to date
+    // incrementally by make() and unmake().
+    #[allow(dead_code)]
+    pub fn zobrist_key(&self) -> ZobristKey {
+        self.game_state.zobrist_key
+    }
+
+    // Returns the game phase, as it is kept up to date incrementally by
+    // make() and unmake(): MAX_PHASE means the middlegame with all pieces
+    // present, 0 means a pawn endgame. Promotions can push the sum of the
+    // phase weights above the maximum, so it is capped here.
+    pub fn phase(&self) -> i16 {
+        self.game_state.phase.min(MAX_PHASE)
+    }
+
+    // Computes the Zobrist key of the position from the pieces, castling
+    // permissions, side to move and en-passant square, without using the
+    // incrementally updated key. This is used to validate that key.
+    pub fn compute_zobrist_from_scratch(&self) -> ZobristKey {
+        self.init_zobrist_key()
+    }
 }
 
 // Private board functions (for initializating on startup)
@@ -180,10 +223,12 @@ impl Board {
         // later be updated incrementally.
         self.piece_list = self.init_piece_list();
         self.game_state.zobrist_key = self.init_zobrist_key();
+        self.game_state.pawn_key = self.init_pawn_key();
 
         let psqt = psqt::apply(self);
         self.game_state.psqt[Sides::WHITE] = psqt.0;
         self.game_state.psqt[Sides::BLACK] = psqt.1;
+        self.game_state.phase = self.init_phase();
     }
 
     // Gather the pieces for each side into their own bitboard.
@@ -280,4 +325,33 @@ impl Board {
         // Done; return the key.
         key
     }
+
+    // Initialize the game phase: the sum of the phase weights of all the
+    // pieces on the board. It will later be updated incrementally.
+    fn init_phase(&self) -> i16 {
+        let mut phase: i16 = 0;
+
+        for side in [Sides::WHITE, Sides::BLACK] {
+            for (bb, weight) in self.bb_pieces[side].iter().zip(PHASE_WEIGHTS.iter()) {
+                phase += bb.count_ones() as i16 * weight;
+            }
+        }
+
+        phase
+    }
+
+    // Initialize the pawn hash. This is a zobrist key that only contains
+    // the pawns, so positions with the same pawn structure have the same
+    // pawn key. It will later be updated incrementally.
+    fn init_pawn_key(&self) -> ZobristKey {
+        let mut key: u64 = 0;
+
+        for side in [Sides::WHITE, Sides::BLACK] {
+            for square in bits::squares(self.bb_pieces[side][Pieces::PAWN]) {
+                key ^= self.zr.piece(side, Pieces::PAWN, square);
+            }
+        }
+
+        key
+    }
 }
@@ -42,6 +42,7 @@ pub const PIECE_NAME: [&str; NrOf::PIECE_TYPES + 1] =
     ["King", "Queen", "Rook", "Bishop", "Knight", "Pawn", "-"];
 pub const PIECE_CHAR_CAPS: [&str; NrOf::PIECE_TYPES + 1] = ["K", "Q", "R", "B", "N", "", "_"];
 pub const PIECE_CHAR_SMALL: [&str; NrOf::PIECE_TYPES + 1] = ["k", "q", "r", "b", "n", "", ""];
+pub const PIECE_CHAR_FEN: [char; NrOf::PIECE_TYPES + 1] = ['K', 'Q', 'R', 'B', 'N', 'P', '.'];
 
 pub struct Pieces;
 impl Pieces {
@@ -58,6 +59,7 @@ pub struct Files;
 impl Files {
     pub const A: usize = 0;
     pub const B: usize = 1;
+    pub const C: usize = 2;
     pub const G: usize = 6;
     pub const H: usize = 7;
 }
@@ -156,10 +158,128 @@ const fn init_bb_squares() -> TBBSquares {
 pub const BB_FILES: TBBFiles = init_bb_files();
 pub const BB_RANKS: TBBRanks = init_bb_ranks();
 pub const BB_SQUARES: TBBSquares = init_bb_squares();
+pub const BB_DARK_SQUARES: Bitboard = 0xAA55_AA55_AA55_AA55; // A1 is a dark square
 
 // Piece location: (file, rank)
 pub type Location = (u8, u8);
 
+// Square as a newtype, for code that does arithmetic on coordinates. The
+// rest of the engine uses Square, which is a plain index; converting
+// between the two costs nothing. (The name Sq keeps it apart from that
+// index type.)
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Sq(u8);
+
+impl Sq {
+    #[inline]
+    pub const fn new(square: Square) -> Self {
+        Self(square as u8)
+    }
+
+    #[inline]
+    pub const fn from_file_rank(file: usize, rank: usize) -> Self {
+        Self((rank * 8 + file) as u8)
+    }
+
+    #[inline]
+    pub const fn index(self) -> Square {
+        self.0 as Square
+    }
+
+    #[inline]
+    pub const fn file(self) -> usize {
+        (self.0 % 8) as usize
+    }
+
+    #[inline]
+    pub const fn rank(self) -> usize {
+        (self.0 / 8) as usize
+    }
+
+    // Mirrors the square vertically (A1 <=> A8), for looking at the board
+    // from black's point of view.
+    #[inline]
+    pub const fn flip(self) -> Self {
+        Self(self.0 ^ 56)
+    }
+
+    // After a pawn double step to this square, returns the square the pawn
+    // passed over: the en-passant square. For an en-passant square, it
+    // returns the square of the pawn that can be captured. (This works for
+    // both sides, because it's one rank up for white and down for black.)
+    #[inline]
+    pub const fn en_passant_partner(self) -> Self {
+        Self(self.0 ^ 8)
+    }
+}
+
+// Reasons why make_uci() can refuse to execute a move.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MoveError {
+    NotAMove,          // The string is not a move in long algebraic notation.
+    NotPseudoLegal,    // The move can't be played in this position at all.
+    LeavesKingInCheck, // The move would leave the own king in check.
+}
+
+// Reasons why fen_read() can refuse an FEN-string. The numbers are the
+// same as the FEN error codes in ENGINE_RUN_ERRORS.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FenError {
+    NrOfParts = 0,      // The FEN-string doesn't have four to six parts.
+    Pieces = 1,         // Unknown characters, or ranks of the wrong length.
+    Color = 2,          // The side to move is not 'w' or 'b'.
+    Castling = 3,       // Unknown characters in the castling permissions.
+    EnPassant = 4,      // Not '-', or a square on the 3rd or 6th rank.
+    HalfmoveClock = 5,  // Not a number, or above the 50-move rule.
+    FullmoveNumber = 6, // Not a number, or above the maximum game length.
+}
+
+impl From<FenError> for u8 {
+    fn from(e: FenError) -> Self {
+        e as u8
+    }
+}
+
+// The outcome of adjudicating a position without searching it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Adjudication {
+    DeadPosition, // Neither side can ever checkmate, by any series of legal moves.
+}
+
+// The reasons why the material on the board can be insufficient. The
+// first three are dead positions as listed in the FIDE laws: neither side
+// can ever checkmate. With the last one, mate is possible, but it can't
+// be forced against correct defense.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InsufficientMaterial {
+    BareKings,        // K vs. K
+    SingleMinor,      // KB vs. K or KN vs. K
+    SameColorBishops, // Only bishops, all on squares of the same color
+    NoForcedMate,     // For example KNN vs. K, or KB vs. KB on opposite colors
+}
+
+// Weights for Board::drawishness(). The result is capped at
+// Drawishness::MAX, which is also the value for a dead position.
+pub struct Drawishness;
+impl Drawishness {
+    pub const MAX: u8 = 100; // Neither side can win anymore
+    pub const NO_FORCED_MATE: u8 = 80; // Mate is possible, but can't be forced
+    pub const PURE_OPPOSITE_BISHOPS: u8 = 50; // Only bishops on opposite colors and pawns
+    pub const OPPOSITE_BISHOPS: u8 = 20; // Bishops on opposite colors, with other pieces
+    pub const NO_PASSED_PAWNS: u8 = 20; // No pawn can run for promotion
+    pub const PER_PASSED_PAWN: u8 = 15; // Subtracted for each passed pawn
+}
+
+// Information to quickly see if a move gives check, without making it.
+// It is computed once per position, for the side to move.
+#[derive(Copy, Clone)]
+pub struct CheckInfo {
+    pub king_square: Square,                          // Opponent's king square
+    pub check_squares: [Bitboard; NrOf::PIECE_TYPES], // Squares giving check, per piece
+    pub discoverers: Bitboard,                        // Pieces that can give discovered check
+}
+
 // This enum holds the direction in which a ray of a slider piece can point.
 #[derive(Copy, Clone)]
 pub enum Direction {
@@ -172,3 +292,49 @@ pub enum Direction {
     DownRight,
     DownLeft,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sq_file_and_rank() {
+        for square in RangeOf::SQUARES {
+            let sq = Sq::new(square);
+            assert_eq!(Sq::from_file_rank(sq.file(), sq.rank()), sq);
+            assert_eq!(sq.index(), square);
+        }
+        assert_eq!(Sq::new(Squares::A1).file(), Files::A);
+        assert_eq!(Sq::new(Squares::H8).rank(), Ranks::R8);
+        assert_eq!(Sq::from_file_rank(Files::H, Ranks::R1).index(), Squares::H1);
+    }
+
+    #[test]
+    fn sq_flip() {
+        // The examples from the PSQT explanation in evaluation/psqt.rs.
+        assert_eq!(Sq::new(Squares::A1).flip().index(), 56);
+        assert_eq!(Sq::new(Squares::H8).flip().index(), 7);
+        assert_eq!(Sq::new(Squares::E8).flip().index(), 4);
+        assert_eq!(Sq::new(Squares::E1).flip().index(), 60);
+
+        for square in RangeOf::SQUARES {
+            let sq = Sq::new(square);
+            assert_eq!(sq.flip().flip(), sq);
+            assert_eq!(sq.flip().file(), sq.file());
+            assert_eq!(sq.flip().rank(), 7 - sq.rank());
+        }
+    }
+
+    #[test]
+    fn sq_en_passant_partner() {
+        // a2-a4 passes over a3; h7-h5 passes over h6.
+        let a3 = Sq::new(Squares::A3);
+        let a4 = Sq::from_file_rank(Files::A, Ranks::R4);
+        let h5 = Sq::from_file_rank(Files::H, Ranks::R5);
+        let h6 = Sq::new(Squares::H6);
+        assert_eq!(a4.en_passant_partner(), a3);
+        assert_eq!(a3.en_passant_partner(), a4);
+        assert_eq!(h5.en_passant_partner(), h6);
+        assert_eq!(h6.en_passant_partner(), h5);
+    }
+}
@@ -29,7 +29,7 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 // move.
 
 use super::{
-    defs::{Files, Pieces, Ranks, Squares, BB_SQUARES},
+    defs::{FenError, Files, Pieces, Ranks, Sq, Squares, BB_SQUARES},
     Board,
 };
 use crate::{
@@ -42,6 +42,7 @@ use std::ops::RangeInclusive;
 /** Definitions used by the FEN-reader */
 const NR_OF_FEN_PARTS: usize = 6;
 const SHORT_FEN_PARTS: usize = 4;
+const DEFAULT_MOVE_COUNTERS: [&str; NR_OF_FEN_PARTS - SHORT_FEN_PARTS] = ["0", "1"];
 const LIST_OF_PIECES: &str = "kqrbnpKQRBNP";
 const EP_SQUARES_WHITE: RangeInclusive<Square> = Squares::A3..=Squares::H3;
 const EP_SQUARES_BLACK: RangeInclusive<Square> = Squares::A6..=Squares::H6;
@@ -53,7 +54,7 @@ const EM_DASH: char = '–';
 const SPACE: char = ' ';
 
 type FenPartParser = fn(board: &mut Board, part: &str) -> bool;
-type FenResult = Result<(), u8>;
+type FenResult = Result<(), FenError>;
 
 impl Board {
     // This function reads a provided FEN-string or uses the default position.
@@ -68,19 +69,35 @@ impl Board {
         .map(|s| s.to_string())
         .collect();
 
-        if fen_parts.len() == SHORT_FEN_PARTS {
-            fen_parts.append(&mut vec![String::from("0"), String::from("1")]);
+        // The move counters are often left out. Whichever of them are
+        // missing get their default value. The ones that are present are
+        // still validated.
+        if (SHORT_FEN_PARTS..NR_OF_FEN_PARTS).contains(&fen_parts.len()) {
+            let missing = &DEFAULT_MOVE_COUNTERS[fen_parts.len() - SHORT_FEN_PARTS..];
+            fen_parts.extend(missing.iter().map(|s| s.to_string()));
         }
 
         // Check the number of fen parts.
         let nr_of_parts_ok = fen_parts.len() == NR_OF_FEN_PARTS;
 
         // Set the initial result.
-        let mut result: FenResult = if nr_of_parts_ok { Ok(()) } else { Err(0) };
+        let mut result: FenResult = if nr_of_parts_ok {
+            Ok(())
+        } else {
+            Err(FenError::NrOfParts)
+        };
 
         if nr_of_parts_ok {
-            // Create an array of function pointers; one parsing function per part.
-            let fen_parsers: [FenPartParser; 6] = [pieces, color, castling, ep, hmc, fmn];
+            // Create an array of function pointers; one parsing function
+            // per part, with the error to report if that part is wrong.
+            let fen_parsers: [(FenPartParser, FenError); 6] = [
+                (pieces, FenError::Pieces),
+                (color, FenError::Color),
+                (castling, FenError::Castling),
+                (ep, FenError::EnPassant),
+                (hmc, FenError::HalfmoveClock),
+                (fmn, FenError::FullmoveNumber),
+            ];
 
             // Create a new board so we don't destroy the original.
             let mut new_board = self.clone();
@@ -89,10 +106,10 @@ impl Board {
             // Parse all the parts and check if each one succeeds.
             let mut i: usize = 0;
             while i < NR_OF_FEN_PARTS && result == Ok(()) {
-                let parser = &fen_parsers[i];
+                let (parser, error) = &fen_parsers[i];
                 let part = &fen_parts[i];
                 let part_ok = parser(&mut new_board, part);
-                result = if part_ok { Ok(()) } else { Err(i as u8 + 1) };
+                result = if part_ok { Ok(()) } else { Err(*error) };
                 i += 1;
             }
 
@@ -119,7 +136,13 @@ fn pieces(board: &mut Board, part: &str) -> bool {
 
     // Parse each character; it should be a piece, square count, or splitter.
     for c in part.chars() {
-        let square = ((rank * 8) + file) as usize;
+        // A piece can't be put beyond the H-file.
+        if LIST_OF_PIECES.contains(c) && file > Files::H as u8 {
+            result = false;
+            break;
+        }
+
+        let square = Sq::from_file_rank(file as usize, rank as usize).index();
         match c {
             'k' => board.bb_pieces[Sides::BLACK][Pieces::KING] |= BB_SQUARES[square],
             'q' => board.bb_pieces[Sides::BLACK][Pieces::QUEEN] |= BB_SQUARES[square],
@@ -139,8 +162,9 @@ fn pieces(board: &mut Board, part: &str) -> bool {
                 }
             }
             SPLITTER => {
-                result = file == 8;
-                rank -= 1;
+                // The rank must be complete, and there must be a next one.
+                result = file == 8 && rank > Ranks::R1 as u8;
+                rank = rank.saturating_sub(1);
                 file = 0;
             }
             // Unknown character: result becomes false.
@@ -158,7 +182,9 @@ fn pieces(board: &mut Board, part: &str) -> bool {
         }
     }
 
-    result
+    // All eight ranks must have been parsed, and the last one must have
+    // been complete as well.
+    result && rank == Ranks::R1 as u8 && file == 8
 }
 
 // Part 2: Parse color to move: White or Black
@@ -236,7 +262,13 @@ fn ep(board: &mut Board, part: &str) -> bool {
 
         match square {
             Some(s) if EP_SQUARES_WHITE.contains(&s) || EP_SQUARES_BLACK.contains(&s) => {
-                board.game_state.en_passant = Some(s as u8);
+                // Like make(), only keep the ep-square if the side to move
+                // has a pawn that can capture en passant.
+                let ep_square = Sq::new(s);
+                let pawn = ep_square.en_passant_partner().index();
+                if board.en_passant_possible(board.us(), pawn) {
+                    board.game_state.en_passant = Some(ep_square);
+                }
                 char_ok += 2;
             }
             Some(_) | None => (),
@@ -283,3 +315,82 @@ fn fmn(board: &mut Board, part: &str) -> bool {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defs::{Castling, Sides};
+
+    const FOUR_PARTS: &str = "r3k2r/8/8/8/8/8/8/R3K2R b Kq -";
+
+    #[test]
+    fn missing_move_counters_get_defaults() {
+        let mut board = Board::new();
+        assert_eq!(board.fen_read(Some(FOUR_PARTS)), Ok(()));
+        assert_eq!(board.game_state.halfmove_clock, 0);
+        assert_eq!(board.game_state.fullmove_number, 1);
+        assert_eq!(board.us(), Sides::BLACK);
+        assert_eq!(board.game_state.castling, Castling::WK | Castling::BQ);
+        assert_eq!(board.occupancy().count_ones(), 6);
+
+        let mut full = Board::new();
+        assert_eq!(full.fen_read(Some(&format!("{FOUR_PARTS} 0 1"))), Ok(()));
+        assert_eq!(board.game_state.zobrist_key, full.game_state.zobrist_key);
+    }
+
+    #[test]
+    fn missing_fullmove_number_gets_default() {
+        let mut board = Board::new();
+        assert_eq!(board.fen_read(Some(&format!("{FOUR_PARTS} 12"))), Ok(()));
+        assert_eq!(board.game_state.halfmove_clock, 12);
+        assert_eq!(board.game_state.fullmove_number, 1);
+    }
+
+    #[test]
+    fn present_move_counters_are_validated() {
+        let mut board = Board::new();
+        assert_eq!(board.fen_read(None), Ok(()));
+        let fen = format!("{FOUR_PARTS} x");
+        assert_eq!(board.fen_read(Some(&fen)), Err(FenError::HalfmoveClock));
+        let fen = format!("{FOUR_PARTS} 0 y");
+        assert_eq!(board.fen_read(Some(&fen)), Err(FenError::FullmoveNumber));
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b Kq";
+        assert_eq!(board.fen_read(Some(fen)), Err(FenError::NrOfParts));
+
+        // A FEN-string that is refused leaves the board as it was.
+        assert_eq!(board.occupancy().count_ones(), 32);
+    }
+
+    #[test]
+    fn malformed_parts_are_refused() {
+        const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+        let cases = [
+            // Unknown piece, rank too long or too short, missing rank.
+            (
+                "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::Pieces,
+            ),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::Pieces,
+            ),
+            (
+                "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::Pieces,
+            ),
+            ("rnbqkbnr/8/8/8/8/8/RNBQKBNR w KQkq - 0 1", FenError::Pieces),
+            (&format!("{START} x KQkq - 0 1"), FenError::Color),
+            (&format!("{START} wb KQkq - 0 1"), FenError::Color),
+            (&format!("{START} w KQxq - 0 1"), FenError::Castling),
+            (&format!("{START} w KQkqK - 0 1"), FenError::Castling),
+            (&format!("{START} w KQkq e4 0 1"), FenError::EnPassant),
+            (&format!("{START} w KQkq i6 0 1"), FenError::EnPassant),
+            (&format!("{START} w KQkq e - 0 1"), FenError::NrOfParts),
+        ];
+
+        for (fen, error) in cases {
+            let mut board = Board::new();
+            assert_eq!(board.fen_read(Some(fen)), Err(error), "{fen}");
+        }
+    }
+}
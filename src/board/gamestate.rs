@@ -22,7 +22,7 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 use crate::{
-    board::defs::{Pieces, PIECE_NAME, SQUARE_NAME},
+    board::defs::{Pieces, Sq, PIECE_NAME, SQUARE_NAME},
     defs::Sides,
     misc::print,
     movegen::defs::Move,
@@ -38,11 +38,14 @@ pub struct GameState {
     pub active_color: u8,
     pub castling: u8,
     pub halfmove_clock: u8,
-    pub en_passant: Option<u8>,
+    pub en_passant: Option<Sq>,
     pub fullmove_number: u16,
     pub zobrist_key: u64,
+    pub pawn_key: u64,
     pub psqt: [i16; Sides::BOTH],
+    pub phase: i16,
     pub next_move: Move,
+    pub in_check: Option<bool>,
 }
 
 impl GameState {
@@ -54,14 +57,17 @@ impl GameState {
             halfmove_clock: 0,
             fullmove_number: 0,
             zobrist_key: 0,
+            pawn_key: 0,
             psqt: [0; Sides::BOTH],
+            phase: 0,
             next_move: Move::new(0),
+            in_check: None,
         }
     }
 
     pub fn as_string(&self) -> String {
         let ep = if let Some(x) = self.en_passant {
-            SQUARE_NAME[x as usize]
+            SQUARE_NAME[x.index()]
         } else {
             "-"
         };
@@ -24,12 +24,16 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 // playmove.rs contains make() and unamke() for move execution and reversal.
 
 use super::{
-    defs::{Pieces, Squares, BB_SQUARES},
+    defs::{MoveError, Pieces, Sq, Squares, ZobristKey, BB_SQUARES},
     Board,
 };
 use crate::{
     defs::{Castling, NrOf, Piece, Side, Sides, Square},
-    movegen::{defs::Move, MoveGenerator},
+    misc::parse,
+    movegen::{
+        defs::{Move, MoveList, MoveType},
+        MoveGenerator,
+    },
 };
 
 // Castling Permissions Per Square
@@ -116,12 +120,16 @@ impl Board {
 
             // After an en-passant maneuver, the opponent's pawn must also be removed.
             if en_passant {
-                self.remove_piece(opponent, Pieces::PAWN, to ^ 8);
+                let captured_pawn = Sq::new(to).en_passant_partner().index();
+                self.remove_piece(opponent, Pieces::PAWN, captured_pawn);
             }
 
-            // A double-step is the only move that sets the ep-square.
-            if double_step {
-                self.set_ep_square(to ^ 8);
+            // A double-step is the only move that sets the ep-square. It
+            // is only set if the opponent has a pawn that could capture
+            // en passant; otherwise the position is the same as one
+            // without an ep-square, and should have the same key.
+            if double_step && self.en_passant_possible(opponent, to) {
+                self.set_ep_square(Sq::new(to).en_passant_partner().index());
             }
         }
 
@@ -152,7 +160,12 @@ impl Board {
 
         /*** Validating move: see if "us" is in check. If so, undo everything. ***/
         let is_legal = !mg.square_attacked(self, opponent, self.king_square(us));
-        if !is_legal {
+        if is_legal {
+            // The move is legal. Find out if it gives check, so the new
+            // side to move knows if it is in check without looking again.
+            let gives_check = mg.square_attacked(self, us, self.king_square(opponent));
+            self.game_state.in_check = Some(gives_check);
+        } else {
             self.unmake();
         }
 
@@ -216,7 +229,8 @@ impl Board {
 
         // If this was an e-passant move, put the opponent's pawn back
         if en_passant {
-            put_piece(self, opponent, Pieces::PAWN, to ^ 8);
+            let captured_pawn = Sq::new(to).en_passant_partner().index();
+            put_piece(self, opponent, Pieces::PAWN, captured_pawn);
         }
     }
 }
@@ -250,6 +264,119 @@ fn reverse_move(board: &mut Board, side: Side, piece: Piece, remove: Square, put
     put_piece(board, side, piece, put);
 }
 
+// Make_uci() is a slower variant of make() that reports why a move was
+// refused. It is meant for moves coming from outside of the engine, such
+// as the ones in the "position" command. The search keeps using make().
+impl Board {
+    // Makes a move given in long algebraic notation, such as "e2e4" or
+    // "e7e8q", and returns the full move. Castling is given as the king's
+    // move, such as "e1g1". (The engine doesn't play Chess960, so the
+    // king-takes-rook notation used there is not accepted.)
+    pub fn make_uci(&mut self, uci: &str, mg: &MoveGenerator) -> Result<Move, MoveError> {
+        let (from, to, promoted) =
+            parse::algebraic_move_to_number(uci).map_err(|_| MoveError::NotAMove)?;
+
+        let mut ml = MoveList::new();
+        mg.generate_moves(self, &mut ml, MoveType::All);
+
+        // Find the pseudo-legal move that matches the string.
+        let m = (0..ml.len())
+            .map(|i| ml.get_move(i))
+            .find(|m| m.from() == from && m.to() == to && m.promoted() == promoted)
+            .ok_or(MoveError::NotPseudoLegal)?;
+
+        if self.make(m, mg) {
+            Ok(m)
+        } else {
+            Err(MoveError::LeavesKingInCheck)
+        }
+    }
+}
+
+// Zobrist_key_after() returns the Zobrist key the board would have after
+// the given move, without making it. It follows the same steps as make(),
+// but only on the key. The move is not checked for legality, so for an
+// illegal move the key of the (illegal) resulting position is returned.
+impl Board {
+    #[allow(dead_code)]
+    pub fn zobrist_key_after(&self, m: Move) -> ZobristKey {
+        let mut key = self.game_state.zobrist_key;
+        let us = self.us();
+        let opponent = us ^ 1;
+
+        // Dissect the move.
+        let piece = m.piece();
+        let from = m.from();
+        let to = m.to();
+        let captured = m.captured();
+        let promoted = m.promoted();
+        let castling = m.castling();
+        let double_step = m.double_step();
+        let en_passant = m.en_passant();
+        let mut permissions = self.game_state.castling;
+
+        // Clear the current ep-square.
+        key ^= self.zr.en_passant(self.game_state.en_passant);
+
+        // Remove the captured piece and adjust castling permissions.
+        if captured != Pieces::NONE {
+            key ^= self.zr.piece(opponent, captured, to);
+            if captured == Pieces::ROOK {
+                permissions &= CASTLING_PERMS[to];
+            }
+        }
+
+        // Move the piece, taking promotion into account.
+        let placed = if promoted != Pieces::NONE {
+            promoted
+        } else {
+            piece
+        };
+        key ^= self.zr.piece(us, piece, from);
+        key ^= self.zr.piece(us, placed, to);
+
+        // Remove the pawn captured en passant.
+        if en_passant {
+            let captured_pawn = Sq::new(to).en_passant_partner().index();
+            key ^= self.zr.piece(opponent, Pieces::PAWN, captured_pawn);
+        }
+
+        // Set the new ep-square, or hash "no ep-square" back in.
+        let new_ep = if double_step && self.en_passant_possible(opponent, to) {
+            Some(Sq::new(to).en_passant_partner())
+        } else {
+            None
+        };
+        key ^= self.zr.en_passant(new_ep);
+
+        // King or rook leaving its starting square.
+        if piece == Pieces::KING || piece == Pieces::ROOK {
+            permissions &= CASTLING_PERMS[from];
+        }
+        key ^= self.zr.castling(self.game_state.castling);
+        key ^= self.zr.castling(permissions);
+
+        // Move the castling rook.
+        if castling {
+            let (rook_from, rook_to) = match to {
+                Squares::G1 => (Squares::H1, Squares::F1),
+                Squares::C1 => (Squares::A1, Squares::D1),
+                Squares::G8 => (Squares::H8, Squares::F8),
+                Squares::C8 => (Squares::A8, Squares::D8),
+                _ => panic!("Error moving rook during castling."),
+            };
+            key ^= self.zr.piece(us, Pieces::ROOK, rook_from);
+            key ^= self.zr.piece(us, Pieces::ROOK, rook_to);
+        }
+
+        // Swap the side to move.
+        key ^= self.zr.side(us);
+        key ^= self.zr.side(opponent);
+
+        key
+    }
+}
+
 /*** ======================================================================================= ***/
 
 // This function can be used to check if incrementally updated values are
@@ -259,8 +386,12 @@ fn reverse_move(board: &mut Board, side: Side, piece: Piece, remove: Square, put
 // runs in debug mode.
 
 fn check_incrementals(board: &Board) -> bool {
-    let from_scratch_key = board.init_zobrist_key();
+    let from_scratch_key = board.compute_zobrist_from_scratch();
+    let from_scratch_pawn_key = board.init_pawn_key();
     let from_scratch_psqt = crate::evaluation::psqt::apply(board);
+    let from_scratch_sides = board.init_pieces_per_side_bitboards();
+    let from_scratch_piece_list = board.init_piece_list();
+    let from_scratch_phase = board.init_phase();
     let mut result = true;
 
     // Waterfall: only report first error encountered and skip any others.
@@ -269,6 +400,32 @@ fn check_incrementals(board: &Board) -> bool {
         result = false;
     };
 
+    if result && from_scratch_pawn_key != board.game_state.pawn_key {
+        println!("Check Incrementals: Error in pawn key.");
+        result = false;
+    };
+
+    // The side bitboards must be the union of that side's pieces, and a
+    // square can never be occupied by both sides.
+    let bb_white = board.bb_side[Sides::WHITE];
+    let bb_black = board.bb_side[Sides::BLACK];
+    if result && (from_scratch_sides != (bb_white, bb_black) || bb_white & bb_black != 0) {
+        println!("Check Incrementals: Error in side bitboards.");
+        result = false;
+    };
+
+    // Each square in the piece list must hold the piece that is on that
+    // square according to the piece bitboards.
+    if result && from_scratch_piece_list != board.piece_list {
+        println!("Check Incrementals: Error in piece list.");
+        result = false;
+    };
+
+    if result && from_scratch_phase != board.game_state.phase {
+        println!("Check Incrementals: Error in game phase.");
+        result = false;
+    };
+
     if result && from_scratch_psqt.0 != board.game_state.psqt[Sides::WHITE] {
         println!("Check Incrementals: Error in PSQT for white.");
         result = false;
@@ -281,3 +438,417 @@ fn check_incrementals(board: &Board) -> bool {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::gamestate::GameState;
+
+    // Returns the Zobrist key of the position set up from the FEN-string.
+    fn key_of(fen: &str) -> ZobristKey {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        board.game_state.zobrist_key
+    }
+
+    // Plays the move in the position set up from the FEN-string, and
+    // returns the Zobrist key afterward.
+    fn key_after(fen: &str, uci: &str) -> ZobristKey {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        assert!(board.make_uci(uci, &mg).is_ok());
+        assert!(check_incrementals(&board));
+        board.game_state.zobrist_key
+    }
+
+    fn board(fen: &str) -> Board {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        board
+    }
+
+    // Plays the move in the position set up from the FEN-string, and
+    // checks that this gives the position of the expected FEN-string.
+    // Then takes the move back, and checks that the game state is exactly
+    // as it was. Returns the game state after the move.
+    fn make_and_unmake(fen: &str, uci: &str, expected: &str) -> GameState {
+        let mg = MoveGenerator::new();
+        let mut board = board(fen);
+        let before = board.game_state;
+        assert!(board.make_uci(uci, &mg).is_ok(), "{uci}");
+        let after = board.game_state;
+        assert_eq!(after.zobrist_key, key_of(expected), "{uci}");
+
+        board.unmake();
+        let restored = board.game_state;
+        assert_eq!(restored.castling, before.castling);
+        assert_eq!(restored.en_passant, before.en_passant);
+        assert_eq!(restored.halfmove_clock, before.halfmove_clock);
+        assert_eq!(restored.fullmove_number, before.fullmove_number);
+        assert_eq!(restored.zobrist_key, before.zobrist_key);
+        assert!(check_incrementals(&board));
+        after
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const AFTER_E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq";
+    const BLACK_D4: &str = "rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const BLACK_D4_AFTER_E4: &str = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq";
+
+    #[test]
+    fn double_step_without_capturer_has_no_en_passant_key() {
+        let key = key_after(START, "e2e4");
+        assert_eq!(key, key_of(&format!("{AFTER_E4} - 0 1")));
+        assert_eq!(key, key_of(&format!("{AFTER_E4} e3 0 1")));
+    }
+
+    #[test]
+    fn double_step_with_capturer_has_en_passant_key() {
+        let key = key_after(BLACK_D4, "e2e4");
+        assert_eq!(key, key_of(&format!("{BLACK_D4_AFTER_E4} e3 0 1")));
+        assert_ne!(key, key_of(&format!("{BLACK_D4_AFTER_E4} - 0 1")));
+    }
+
+    // Plays the move in the position set up from the FEN-string. Returns
+    // the result, and the Zobrist key of the position afterward.
+    fn play(fen: &str, uci: &str) -> (Result<Move, MoveError>, ZobristKey) {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        let result = board.make_uci(uci, &mg);
+        (result, board.game_state.zobrist_key)
+    }
+
+    #[test]
+    fn make_uci_promotion() {
+        let (result, key) = play("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8n");
+        let m = result.unwrap();
+        assert_eq!(m.promoted(), Pieces::KNIGHT);
+        assert_eq!(key, key_of("4N3/8/8/8/8/8/k7/4K3 b - - 0 1"));
+    }
+
+    #[test]
+    fn make_uci_castling() {
+        let (result, key) = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1");
+        assert!(result.unwrap().castling());
+        assert_eq!(key, key_of("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"));
+    }
+
+    #[test]
+    fn make_uci_en_passant() {
+        let (result, key) = play(&format!("{BLACK_D4_AFTER_E4} e3 0 1"), "d4e3");
+        assert!(result.unwrap().en_passant());
+        assert_eq!(
+            key,
+            key_of("rnbqkbnr/ppp1pppp/8/8/8/4p3/PPPP1PPP/RNBQKBNR w KQkq - 0 2")
+        );
+    }
+
+    #[test]
+    fn make_uci_errors() {
+        let (result, key) = play(START, "e2e5");
+        assert_eq!(result.err(), Some(MoveError::NotPseudoLegal));
+        assert_eq!(key, key_of(START));
+        let (result, _) = play(START, "e2");
+        assert_eq!(result.err(), Some(MoveError::NotAMove));
+        let (result, _) = play("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1", "e1d2");
+        assert_eq!(result.err(), Some(MoveError::LeavesKingInCheck));
+    }
+
+    #[test]
+    fn zobrist_key_after_matches_make() {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        let mg = MoveGenerator::new();
+        let mut rng = SmallRng::seed_from_u64(931);
+        let fens = [
+            START,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+
+        // Play random games, and compare the preview of every legal move
+        // with the key after actually making it.
+        for fen in fens {
+            for _ in 0..10 {
+                let mut board = Board::new();
+                assert!(board.fen_read(Some(fen)).is_ok());
+
+                for _ in 0..80 {
+                    let legal = board.legal_moves(&mg);
+                    if legal.len() == 0 {
+                        break;
+                    }
+
+                    for i in 0..legal.len() {
+                        let m = legal.get_move(i);
+                        let preview = board.zobrist_key_after(m);
+                        assert!(board.make(m, &mg));
+                        assert_eq!(preview, board.game_state.zobrist_key, "{}", m.as_string());
+                        board.unmake();
+                    }
+
+                    let m = legal.get_move(rng.gen_range(0..legal.len()));
+                    assert!(board.make(m, &mg));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cached_in_check_matches_square_attacked() {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        let mg = MoveGenerator::new();
+        let mut rng = SmallRng::seed_from_u64(959);
+        let fens = [
+            START,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            // En passant that gives check, and that exposes the own king.
+            "8/8/8/1k1pP2R/8/8/8/4K3 w - d6 0 1",
+            "8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+        let (mut castles, mut en_passants, mut checks) = (0, 0, 0);
+
+        // Play random games. After every legal move, the cached in-check
+        // status must be the same as looking for an attack on the king.
+        for fen in fens {
+            for _ in 0..10 {
+                let mut board = Board::new();
+                assert!(board.fen_read(Some(fen)).is_ok());
+                let attacked =
+                    |b: &Board| mg.square_attacked(b, b.opponent(), b.king_square(b.us()));
+                assert_eq!(board.in_check(&mg), attacked(&board));
+
+                for _ in 0..80 {
+                    let legal = board.legal_moves(&mg);
+                    if legal.len() == 0 {
+                        break;
+                    }
+
+                    for i in 0..legal.len() {
+                        let m = legal.get_move(i);
+                        assert!(board.make(m, &mg));
+                        let expected = attacked(&board);
+                        assert_eq!(
+                            board.game_state.in_check,
+                            Some(expected),
+                            "{}",
+                            m.as_string()
+                        );
+                        assert_eq!(board.in_check(&mg), expected);
+                        castles += m.castling() as usize;
+                        en_passants += m.en_passant() as usize;
+                        checks += expected as usize;
+                        board.unmake();
+                        assert_eq!(board.in_check(&mg), attacked(&board));
+                    }
+
+                    let m = legal.get_move(rng.gen_range(0..legal.len()));
+                    assert!(board.make(m, &mg));
+                }
+            }
+        }
+
+        assert!(castles > 0 && en_passants > 0 && checks > 0);
+    }
+
+    const CASTLING: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn king_move_clears_both_castling_rights() {
+        let after = make_and_unmake(CASTLING, "e1e2", "r3k2r/8/8/8/8/8/4K3/R6R b kq - 1 1");
+        assert_eq!(after.castling, Castling::BK | Castling::BQ);
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        let after = make_and_unmake(fen, "e8d8", "r2k3r/8/8/8/8/8/8/R3K2R w KQ - 1 2");
+        assert_eq!(after.castling, Castling::WK | Castling::WQ);
+    }
+
+    #[test]
+    fn rook_move_clears_its_castling_right() {
+        let after = make_and_unmake(CASTLING, "h1h2", "r3k2r/8/8/8/8/8/7R/R3K3 b Qkq - 1 1");
+        assert_eq!(after.castling, Castling::WQ | Castling::BK | Castling::BQ);
+    }
+
+    #[test]
+    fn captured_rook_loses_its_castling_right() {
+        // The bishop takes the rook on a1: white can't castle queenside.
+        let fen = "r3k2r/8/8/8/8/8/1b6/R3K2R b KQkq - 0 1";
+        let after = make_and_unmake(fen, "b2a1", "r3k2r/8/8/8/8/8/8/b3K2R w Kkq - 0 2");
+        assert_eq!(after.castling, Castling::WK | Castling::BK | Castling::BQ);
+
+        // Rook takes rook: both queenside rights are gone.
+        let after = make_and_unmake(CASTLING, "a1a8", "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+        assert_eq!(after.castling, Castling::WK | Castling::BK);
+    }
+
+    #[test]
+    fn en_passant_square_lasts_one_ply() {
+        let mg = MoveGenerator::new();
+        let e3 = parse::algebraic_square_to_number("e3").map(Sq::new);
+
+        // After e2e4, black's d4-pawn may take en passant on e3.
+        let after = make_and_unmake(BLACK_D4, "e2e4", &format!("{BLACK_D4_AFTER_E4} e3 0 1"));
+        assert_eq!(after.en_passant, e3);
+
+        // Any next move clears the square again.
+        let mut board = board(BLACK_D4);
+        assert!(board.make_uci("e2e4", &mg).is_ok());
+        assert!(board.make_uci("g8f6", &mg).is_ok());
+        assert_eq!(board.game_state.en_passant, None);
+        board.unmake();
+        assert_eq!(board.game_state.en_passant, e3);
+
+        // So the capture is legal right away, but not a move pair later.
+        assert!(board.make_uci("d4e3", &mg).is_ok());
+        board.unmake();
+        assert!(board.make_uci("g8f6", &mg).is_ok());
+        assert!(board.make_uci("g1f3", &mg).is_ok());
+        assert_eq!(
+            board.make_uci("d4e3", &mg).err(),
+            Some(MoveError::NotPseudoLegal)
+        );
+    }
+
+    #[test]
+    fn halfmove_clock_counts_and_resets() {
+        let mg = MoveGenerator::new();
+
+        // Quiet moves by pieces count up, a capture resets the clock, and
+        // unmake() counts back down across the capture.
+        let mut board = board("r3k3/8/8/3p4/8/8/8/R3K3 w - - 5 30");
+        let mut clocks = vec![];
+        for uci in ["a1a2", "a8a6", "a2a6"] {
+            assert!(board.make_uci(uci, &mg).is_ok(), "{uci}");
+            clocks.push(board.game_state.halfmove_clock);
+        }
+        assert_eq!(clocks, [6, 7, 0]);
+        for clock in [7, 6, 5] {
+            board.unmake();
+            assert_eq!(board.game_state.halfmove_clock, clock);
+        }
+
+        // Pawn moves reset the clock: a push, en passant and a promotion.
+        let after = make_and_unmake(
+            "r3k3/8/8/3p4/8/8/8/R3K3 b - - 5 30",
+            "d5d4",
+            "r3k3/8/8/8/3p4/8/8/R3K3 w - - 0 31",
+        );
+        assert_eq!(after.halfmove_clock, 0);
+        let after = make_and_unmake(
+            &format!("{BLACK_D4_AFTER_E4} e3 9 1"),
+            "d4e3",
+            "rnbqkbnr/ppp1pppp/8/8/8/4p3/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        );
+        assert_eq!(after.halfmove_clock, 0);
+        let after = make_and_unmake(
+            "8/4P3/8/8/8/8/k7/4K3 w - - 9 1",
+            "e7e8q",
+            "4Q3/8/8/8/8/8/k7/4K3 b - - 0 1",
+        );
+        assert_eq!(after.halfmove_clock, 0);
+    }
+
+    // Positions for the random games below: castling, en passant,
+    // promotions with and without capture, and checks.
+    const RANDOM_GAME_FENS: [&str; 5] = [
+        START,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+
+    // Plays random games from each position, and calls the check with
+    // the board after every move that is made, and after unmaking it.
+    fn random_games(seed: u64, mut check: impl FnMut(&Board, Move)) {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        let mg = MoveGenerator::new();
+        let mut rng = SmallRng::seed_from_u64(seed);
+
+        for fen in RANDOM_GAME_FENS {
+            for _ in 0..10 {
+                let mut board = board(fen);
+
+                for _ in 0..100 {
+                    let legal = board.legal_moves(&mg);
+                    if legal.len() == 0 {
+                        break;
+                    }
+
+                    let m = legal.get_move(rng.gen_range(0..legal.len()));
+                    assert!(board.make(m, &mg));
+                    check(&board, m);
+                    if rng.gen_bool(0.1) {
+                        board.unmake();
+                        check(&board, m);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn occupancy_is_the_union_of_the_pieces() {
+        random_games(945, |board, m| {
+            let white = board.bb_pieces[Sides::WHITE].iter().fold(0, |bb, p| bb | p);
+            let black = board.bb_pieces[Sides::BLACK].iter().fold(0, |bb, p| bb | p);
+            assert_eq!(board.bb_side[Sides::WHITE], white, "{}", m.as_string());
+            assert_eq!(board.bb_side[Sides::BLACK], black, "{}", m.as_string());
+            assert_eq!(board.occupancy(), white | black, "{}", m.as_string());
+            assert_eq!(white & black, 0);
+        });
+    }
+
+    #[test]
+    fn zobrist_key_matches_a_fresh_computation() {
+        for fen in RANDOM_GAME_FENS {
+            let board = board(fen);
+            assert_eq!(board.zobrist_key(), board.compute_zobrist_from_scratch());
+        }
+
+        random_games(965, |board, m| {
+            let key = board.compute_zobrist_from_scratch();
+            assert_eq!(board.zobrist_key(), key, "{}", m.as_string());
+        });
+    }
+
+    #[test]
+    fn piece_list_matches_the_bitboards() {
+        random_games(989, |board, m| {
+            for (square, bb_square) in BB_SQUARES.iter().enumerate() {
+                let on_square = |side: Side| {
+                    (0..NrOf::PIECE_TYPES).find(|&p| board.bb_pieces[side][p] & bb_square > 0)
+                };
+                let expected = on_square(Sides::WHITE)
+                    .or(on_square(Sides::BLACK))
+                    .unwrap_or(Pieces::NONE);
+                assert_eq!(board.piece_list[square], expected, "{}", m.as_string());
+            }
+        });
+    }
+
+    #[test]
+    fn phase_matches_a_fresh_computation() {
+        let mut promotions = 0;
+
+        random_games(993, |board, m| {
+            assert_eq!(
+                board.game_state.phase,
+                board.init_phase(),
+                "{}",
+                m.as_string()
+            );
+            promotions += (m.promoted() != Pieces::NONE) as usize;
+        });
+
+        // A promotion adds the phase of the new piece.
+        assert!(promotions > 0);
+    }
+}
@@ -0,0 +1,327 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// san.rs converts moves in Standard Algebraic Notation (SAN), such as
+// "Nbd7", "exd5", "e8=Q+" or "O-O", into the engine's own moves, and
+// writes the engine's moves in SAN.
+
+use super::{
+    defs::{Pieces, PIECE_CHAR_CAPS, SQUARE_NAME},
+    Board,
+};
+use crate::{
+    board::defs::Files,
+    defs::Piece,
+    misc::parse,
+    movegen::{defs::Move, MoveGenerator},
+};
+
+const ANNOTATIONS: &[char] = &['+', '#', '!', '?'];
+const CASTLE_KINGSIDE: [&str; 2] = ["O-O", "0-0"];
+const CASTLE_QUEENSIDE: [&str; 2] = ["O-O-O", "0-0-0"];
+const CAPTURE: char = 'x';
+const PROMOTION: char = '=';
+
+impl Board {
+    // Converts a move in SAN into a legal move in the current position.
+    // Returns None if the SAN can't be parsed, if it doesn't match a legal
+    // move, or if it is ambiguous.
+    #[allow(dead_code)]
+    pub fn san_to_move(&mut self, san: &str, mg: &MoveGenerator) -> Option<Move> {
+        let san = san.trim().trim_end_matches(ANNOTATIONS);
+        let legal_moves = self.legal_moves(mg);
+        let mut found: Option<Move> = None;
+
+        // Castling only needs to match the side of the board the king
+        // is castling to.
+        let castle_to_file = if CASTLE_KINGSIDE.contains(&san) {
+            Some(Files::G)
+        } else if CASTLE_QUEENSIDE.contains(&san) {
+            Some(Files::C)
+        } else {
+            None
+        };
+
+        let parsed = if castle_to_file.is_none() {
+            Some(parse_san(san)?)
+        } else {
+            None
+        };
+
+        for i in 0..legal_moves.len() {
+            let m = legal_moves.get_move(i);
+            let is_match = match (castle_to_file, &parsed) {
+                (Some(file), _) => {
+                    m.castling() && Board::square_on_file_rank(m.to()).0 as usize == file
+                }
+                (None, Some(p)) => p.matches(m),
+                (None, None) => false,
+            };
+
+            if is_match {
+                // A second match means the SAN is ambiguous.
+                if found.is_some() {
+                    return None;
+                }
+                found = Some(m);
+            }
+        }
+
+        found
+    }
+
+    // Writes a legal move in SAN, including the '+' or '#' suffix. The
+    // from-square is only added (file first, then rank, then both) if
+    // another piece of the same type can also move to the destination.
+    #[allow(dead_code)]
+    pub fn move_to_san(&mut self, m: Move, mg: &MoveGenerator) -> String {
+        let (from, to, piece) = (m.from(), m.to(), m.piece());
+        let is_capture = m.captured() != Pieces::NONE || m.en_passant();
+        let from_name = SQUARE_NAME[from];
+        let mut san = String::new();
+
+        if m.castling() {
+            let is_kingside = Board::square_on_file_rank(to).0 as usize == Files::G;
+            san.push_str(if is_kingside {
+                CASTLE_KINGSIDE[0]
+            } else {
+                CASTLE_QUEENSIDE[0]
+            });
+        } else {
+            san.push_str(PIECE_CHAR_CAPS[piece]);
+
+            if piece == Pieces::PAWN {
+                if is_capture {
+                    san.push_str(&from_name[..1]);
+                }
+            } else {
+                san.push_str(self.disambiguation(m, mg));
+            }
+
+            if is_capture {
+                san.push(CAPTURE);
+            }
+            san.push_str(SQUARE_NAME[to]);
+
+            if m.promoted() != Pieces::NONE {
+                san.push(PROMOTION);
+                san.push_str(PIECE_CHAR_CAPS[m.promoted()]);
+            }
+        }
+
+        if self.gives_checkmate(m, mg) {
+            san.push('#');
+        } else if self.gives_check(m, &self.check_info(mg), mg) {
+            san.push('+');
+        }
+
+        san
+    }
+
+    // Returns the part of the from-square that tells the given move apart
+    // from other legal moves of the same piece type to the same square.
+    fn disambiguation(&mut self, m: Move, mg: &MoveGenerator) -> &'static str {
+        let legal_moves = self.legal_moves(mg);
+        let (file, rank) = Board::square_on_file_rank(m.from());
+        let (mut is_ambiguous, mut same_file, mut same_rank) = (false, false, false);
+
+        for i in 0..legal_moves.len() {
+            let other = legal_moves.get_move(i);
+            if other.piece() == m.piece() && other.to() == m.to() && other.from() != m.from() {
+                let (other_file, other_rank) = Board::square_on_file_rank(other.from());
+                is_ambiguous = true;
+                same_file |= other_file == file;
+                same_rank |= other_rank == rank;
+            }
+        }
+
+        let name = SQUARE_NAME[m.from()];
+        match (is_ambiguous, same_file, same_rank) {
+            (false, _, _) => "",
+            (true, false, _) => &name[..1],
+            (true, true, false) => &name[1..],
+            (true, true, true) => name,
+        }
+    }
+}
+
+// The parts of a SAN move (other than castling) that are used to find the
+// matching legal move.
+struct SanParts {
+    piece: Piece,
+    to: usize,
+    promoted: Piece,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+}
+
+impl SanParts {
+    fn matches(&self, m: Move) -> bool {
+        let (file, rank) = Board::square_on_file_rank(m.from());
+
+        m.piece() == self.piece
+            && m.to() == self.to
+            && m.promoted() == self.promoted
+            && !m.castling()
+            && self.from_file.map_or(true, |f| f == file)
+            && self.from_rank.map_or(true, |r| r == rank)
+    }
+}
+
+fn parse_san(san: &str) -> Option<SanParts> {
+    let mut chars: Vec<char> = san.chars().filter(|c| *c != CAPTURE).collect();
+
+    // The piece letter is always upper case. Without one, it's a pawn.
+    let piece = match chars.first()? {
+        'K' => Pieces::KING,
+        'Q' => Pieces::QUEEN,
+        'R' => Pieces::ROOK,
+        'B' => Pieces::BISHOP,
+        'N' => Pieces::KNIGHT,
+        _ => Pieces::PAWN,
+    };
+    if piece != Pieces::PAWN {
+        chars.remove(0);
+    }
+
+    // A promotion is written as "e8=Q", but "e8Q" is also accepted.
+    let mut promoted = Pieces::NONE;
+    if let Some(last) = chars.last() {
+        if last.is_ascii_alphabetic() && chars.len() > 2 {
+            promoted = parse::promotion_piece_letter_to_number(*last)?;
+            chars.pop();
+            if chars.last() == Some(&PROMOTION) {
+                chars.pop();
+            }
+        }
+    }
+
+    // The destination square is always at the end.
+    if chars.len() < 2 {
+        return None;
+    }
+    let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = parse::algebraic_square_to_number(&destination)?;
+
+    // Whatever remains is the disambiguation: a file, a rank, or both.
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' => from_file = Some(c as u8 - b'a'),
+            '1'..='8' => from_rank = Some(c as u8 - b'1'),
+            _ => return None,
+        }
+    }
+
+    Some(SanParts {
+        piece,
+        to,
+        promoted,
+        from_file,
+        from_rank,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Converts the SAN in the given position, and returns the result in
+    // long algebraic notation.
+    fn convert(fen: &str, san: &str) -> Option<String> {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        board.san_to_move(san, &mg).map(|m| m.as_string())
+    }
+
+    // Converts the SAN to a move, and writes that move back in SAN.
+    fn round_trip(fen: &str, san: &str) -> String {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        let m = board.san_to_move(san, &mg).expect("legal move");
+        board.move_to_san(m, &mg)
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const TWO_KNIGHTS: &str = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+    const CASTLING: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    const PROMOTION: &str = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+    const PAWN_CAPTURE: &str = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1";
+
+    #[test]
+    fn pieces_and_pawns() {
+        assert_eq!(convert(START, "e4").as_deref(), Some("e2e4"));
+        assert_eq!(convert(START, "Nf3").as_deref(), Some("g1f3"));
+        assert_eq!(convert(PAWN_CAPTURE, "exd5").as_deref(), Some("e4d5"));
+        assert_eq!(convert(PAWN_CAPTURE, "exd5!?").as_deref(), Some("e4d5"));
+    }
+
+    #[test]
+    fn disambiguation() {
+        assert_eq!(convert(TWO_KNIGHTS, "Nd2"), None);
+        assert_eq!(convert(TWO_KNIGHTS, "Nbd2").as_deref(), Some("b1d2"));
+        assert_eq!(convert(TWO_KNIGHTS, "Nfd2").as_deref(), Some("f1d2"));
+        assert_eq!(convert(TWO_KNIGHTS, "N1d2"), None);
+    }
+
+    #[test]
+    fn castling_and_promotion() {
+        assert_eq!(convert(CASTLING, "O-O").as_deref(), Some("e1g1"));
+        assert_eq!(convert(CASTLING, "0-0-0").as_deref(), Some("e1c1"));
+        assert_eq!(convert(PROMOTION, "e8=Q+").as_deref(), Some("e7e8q"));
+        assert_eq!(convert(PROMOTION, "e8=N").as_deref(), Some("e7e8n"));
+    }
+
+    #[test]
+    fn illegal_or_invalid() {
+        assert_eq!(convert(START, "Qh5"), None);
+        assert_eq!(convert(START, "e5"), None);
+        assert_eq!(convert(START, "Zz9"), None);
+        assert_eq!(convert(START, ""), None);
+    }
+
+    #[test]
+    fn moves_are_written_in_san() {
+        const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1";
+        const EN_PASSANT: &str = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2";
+        const ROOKS: &str = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        const QUEENS: &str = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+
+        assert_eq!(round_trip(START, "e4"), "e4");
+        assert_eq!(round_trip(START, "Nf3"), "Nf3");
+        assert_eq!(round_trip(PAWN_CAPTURE, "exd5"), "exd5");
+        assert_eq!(round_trip(EN_PASSANT, "exd6"), "exd6");
+        assert_eq!(round_trip(TWO_KNIGHTS, "Nbd2"), "Nbd2");
+        assert_eq!(round_trip(ROOKS, "R1a3"), "R1a3");
+        assert_eq!(round_trip(ROOKS, "Ra8"), "Ra8+");
+        assert_eq!(round_trip(QUEENS, "Qa1b2"), "Qa1b2");
+        assert_eq!(round_trip(CASTLING, "O-O"), "O-O");
+        assert_eq!(round_trip(CASTLING, "O-O-O"), "O-O-O");
+        assert_eq!(round_trip(PROMOTION, "e8=N"), "e8=N");
+        assert_eq!(round_trip(PROMOTION, "e8=Q"), "e8=Q");
+        assert_eq!(round_trip(BACK_RANK, "Ra8"), "Ra8#");
+    }
+}
@@ -21,18 +21,34 @@ You should have received a copy of the GNU General Public License along
 with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
-use super::{defs::Location, Board};
+use super::{
+    defs::{
+        Adjudication, CheckInfo, Drawishness, Files, InsufficientMaterial, Location, RangeOf, Sq,
+        BB_DARK_SQUARES, BB_FILES, BB_SQUARES, PIECE_CHAR_FEN, SQUARE_NAME,
+    },
+    Board,
+};
 use crate::{
-    board::defs::Ranks,
-    defs::{Side, Sides, Square},
+    board::defs::{Pieces, Ranks, Squares},
+    defs::{Bitboard, Castling, NrOf, Side, Sides, Square, EMPTY, MAX_MOVE_RULE},
+    evaluation::{
+        self,
+        defs::{PieceValues, MAX_PHASE, PIECE_VALUES_EG, PIECE_VALUES_MG},
+        pawns,
+    },
+    misc::{bits, print},
+    movegen::{
+        defs::{Move, MoveList, MoveType},
+        MoveGenerator,
+    },
 };
+use std::fmt;
 
 impl Board {
     // Compute on which file and rank a given square is.
     pub fn square_on_file_rank(square: Square) -> Location {
-        let file = (square % 8) as u8; // square mod 8
-        let rank = (square / 8) as u8; // square div 8
-        (file, rank)
+        let sq = Sq::new(square);
+        (sq.file() as u8, sq.rank() as u8)
     }
 
     // Compute if a given square is or isn't on the given rank.
@@ -58,3 +74,806 @@ impl Board {
         }
     }
 }
+
+// Functions inspecting the current position: en passant, check, castling,
+// the legal moves and the check info.
+impl Board {
+    // Returns true if the capturer has a pawn next to the given square, so
+    // it could capture a pawn that just double-stepped to it en passant.
+    // (This doesn't check whether that capture would be legal.)
+    pub fn en_passant_possible(&self, capturer: Side, square: Square) -> bool {
+        let bb_square = BB_SQUARES[square];
+        let neighbours =
+            ((bb_square << 1) & !BB_FILES[Files::A]) | ((bb_square >> 1) & !BB_FILES[Files::H]);
+
+        self.bb_pieces[capturer][Pieces::PAWN] & neighbours > 0
+    }
+
+    // Returns true if the side to move is in check. After make(), this is
+    // known already. Only a position set up from a FEN-string has to be
+    // looked at.
+    pub fn in_check(&self, mg: &MoveGenerator) -> bool {
+        match self.game_state.in_check {
+            Some(in_check) => in_check,
+            None => mg.square_attacked(self, self.opponent(), self.king_square(self.us())),
+        }
+    }
+
+    // Returns true if the given side may castle to the kingside or the
+    // queenside: the right to do so must still exist, the squares between
+    // king and rook must be empty, and the king may not be in check, pass
+    // through an attacked square, or land on one.
+    pub fn can_castle(&self, mg: &MoveGenerator, side: Side, queenside: bool) -> bool {
+        let (right, between, king_path) = match (side, queenside) {
+            (Sides::WHITE, false) => (
+                Castling::WK,
+                BB_SQUARES[Squares::F1] | BB_SQUARES[Squares::G1],
+                [Squares::E1, Squares::F1, Squares::G1],
+            ),
+            (Sides::WHITE, true) => (
+                Castling::WQ,
+                BB_SQUARES[Squares::B1] | BB_SQUARES[Squares::C1] | BB_SQUARES[Squares::D1],
+                [Squares::E1, Squares::D1, Squares::C1],
+            ),
+            (_, false) => (
+                Castling::BK,
+                BB_SQUARES[Squares::F8] | BB_SQUARES[Squares::G8],
+                [Squares::E8, Squares::F8, Squares::G8],
+            ),
+            (_, true) => (
+                Castling::BQ,
+                BB_SQUARES[Squares::B8] | BB_SQUARES[Squares::C8] | BB_SQUARES[Squares::D8],
+                [Squares::E8, Squares::D8, Squares::C8],
+            ),
+        };
+        let opponent = side ^ 1;
+
+        (self.game_state.castling & right > 0)
+            && (self.occupancy() & between == 0)
+            && king_path
+                .iter()
+                .all(|&square| !mg.square_attacked(self, opponent, square))
+    }
+
+    // Generates all the legal moves in the current position.
+    pub fn legal_moves(&mut self, mg: &MoveGenerator) -> MoveList {
+        self.legal_moves_where(mg, |_| true)
+    }
+
+    // Generates the legal captures in the current position, including
+    // captures en passant. Promotions change the material on the board as
+    // well, so they are included, even if they don't capture anything.
+    #[allow(dead_code)]
+    pub fn legal_captures(&mut self, mg: &MoveGenerator) -> MoveList {
+        self.legal_moves_where(mg, |m| {
+            m.captured() != Pieces::NONE || m.en_passant() || m.promoted() != Pieces::NONE
+        })
+    }
+
+    // Generates the legal moves in the current position for which 'keep'
+    // returns true.
+    fn legal_moves_where(&mut self, mg: &MoveGenerator, keep: impl Fn(Move) -> bool) -> MoveList {
+        let mut pseudo_legal = MoveList::new();
+        let mut legal = MoveList::new();
+
+        mg.generate_moves(self, &mut pseudo_legal, MoveType::All);
+        for i in 0..pseudo_legal.len() {
+            let m = pseudo_legal.get_move(i);
+            if keep(m) && self.make(m, mg) {
+                self.unmake();
+                legal.push(m);
+            }
+        }
+
+        legal
+    }
+
+    // Computes the check info for the side to move: the squares from
+    // which each piece type would attack the opponent's king, and our
+    // pieces that are the only blocker between one of our sliders and
+    // that king. (Moving those away gives a discovered check.)
+    pub fn check_info(&self, mg: &MoveGenerator) -> CheckInfo {
+        let us = self.us();
+        let king_square = self.king_square(self.opponent());
+        let occupancy = self.occupancy();
+        let rook = mg.get_slider_attacks(Pieces::ROOK, king_square, occupancy);
+        let bishop = mg.get_slider_attacks(Pieces::BISHOP, king_square, occupancy);
+
+        // A king can never give check itself.
+        let mut check_squares = [EMPTY; NrOf::PIECE_TYPES];
+        check_squares[Pieces::QUEEN] = rook | bishop;
+        check_squares[Pieces::ROOK] = rook;
+        check_squares[Pieces::BISHOP] = bishop;
+        check_squares[Pieces::KNIGHT] = mg.get_non_slider_attacks(Pieces::KNIGHT, king_square);
+        check_squares[Pieces::PAWN] = mg.get_pawn_attacks(self.opponent(), king_square);
+
+        // Our pieces that are the only blocker between one of our sliders
+        // and the opponent's king are discoverers.
+        let mut discoverers = EMPTY;
+        self.for_each_xray(mg, king_square, us, us, |_, blocker| discoverers |= blocker);
+
+        CheckInfo {
+            king_square,
+            check_squares,
+            discoverers,
+        }
+    }
+
+    // Finds the sliders of the attacking side that are lined up with the
+    // king on the given square, behind pieces of the blocking side. For
+    // each of them, f is called with the slider's square and the piece
+    // in between. If there is more than one, the bitboard is empty. If
+    // the blocking side is the king's side, the blockers are pinned. If
+    // it is the attacking side, moving them away gives a discovered check.
+    pub fn for_each_xray(
+        &self,
+        mg: &MoveGenerator,
+        king_square: Square,
+        blocking: Side,
+        attacking: Side,
+        mut f: impl FnMut(Square, Bitboard),
+    ) {
+        let occupancy = self.occupancy();
+        let queens = self.bb_pieces[attacking][Pieces::QUEEN];
+
+        // Remove the blocking pieces from the king's lines, and see which
+        // of the attacking sliders appear. The square in between that
+        // holds a blocking piece is where the two lines meet. If there are
+        // two blocking pieces, the lines don't meet.
+        for piece in [Pieces::ROOK, Pieces::BISHOP] {
+            let attacks = mg.get_slider_attacks(piece, king_square, occupancy);
+            let blockers = attacks & self.bb_side[blocking];
+            let xray = mg.get_slider_attacks(piece, king_square, occupancy ^ blockers);
+            let snipers = xray & !attacks & (self.bb_pieces[attacking][piece] | queens);
+
+            for sniper in bits::squares(snipers) {
+                let between = mg.get_slider_attacks(piece, sniper, occupancy) & attacks;
+                f(sniper, between & blockers);
+            }
+        }
+    }
+}
+
+// Functions inspecting the position after a move. Where needed, they make
+// the move on the board and take it back again, so the board is unchanged
+// when they return.
+impl Board {
+    // Returns true if the given move puts the opponent in check. The move
+    // must be legal, and the check info must be from the current position.
+    // Castling and en-passant moves are rare, and can give check in ways
+    // the check info doesn't cover, so they are made and unmade instead.
+    pub fn gives_check(&mut self, m: Move, ci: &CheckInfo, mg: &MoveGenerator) -> bool {
+        if m.castling() || m.en_passant() {
+            let mut is_check = false;
+            if self.make(m, mg) {
+                is_check = self.in_check(mg);
+                self.unmake();
+            }
+            return is_check;
+        }
+
+        let us = self.us();
+        let from = m.from();
+        let to = m.to();
+        let promoted = m.promoted();
+        let bb_king = BB_SQUARES[ci.king_square];
+        let occupancy = (self.occupancy() ^ BB_SQUARES[from]) | BB_SQUARES[to];
+
+        // Direct check. A promoted piece can give check through the square
+        // the pawn just left, so its attacks use the new occupancy.
+        let direct = if promoted == Pieces::NONE {
+            ci.check_squares[m.piece()] & BB_SQUARES[to] > 0
+        } else if promoted == Pieces::KNIGHT {
+            mg.get_non_slider_attacks(Pieces::KNIGHT, to) & bb_king > 0
+        } else {
+            mg.get_slider_attacks(promoted, to, occupancy) & bb_king > 0
+        };
+
+        if direct {
+            return true;
+        }
+
+        // Discovered check: the piece leaves a line between one of our
+        // sliders and the king, and doesn't move onto that same line.
+        if ci.discoverers & BB_SQUARES[from] > 0 {
+            let queens = self.bb_pieces[us][Pieces::QUEEN];
+            let rooks = (self.bb_pieces[us][Pieces::ROOK] | queens) & !BB_SQUARES[from];
+            let bishops = (self.bb_pieces[us][Pieces::BISHOP] | queens) & !BB_SQUARES[from];
+            let rook = mg.get_slider_attacks(Pieces::ROOK, ci.king_square, occupancy);
+            let bishop = mg.get_slider_attacks(Pieces::BISHOP, ci.king_square, occupancy);
+
+            return (rook & rooks) | (bishop & bishops) > 0;
+        }
+
+        false
+    }
+
+    // Returns true if the given move checkmates the opponent: it gives
+    // check, and the opponent has no legal reply. (Used for the '#'
+    // suffix in SAN notation.)
+    pub fn gives_checkmate(&mut self, m: Move, mg: &MoveGenerator) -> bool {
+        let mut is_mate = false;
+
+        if self.make(m, mg) {
+            is_mate = self.in_check(mg) && self.legal_moves(mg).len() == 0;
+            self.unmake();
+        }
+
+        is_mate
+    }
+}
+
+// Draw detection that can be done by looking at the current position
+// only, without walking the game history.
+impl Board {
+    // Returns true if the position is a draw by insufficient material or
+    // by the 50-move rule. Both are cheap checks, so this function should
+    // be called before scanning the history for repetitions.
+    pub fn is_draw_by_material_and_rule(&mut self, mg: &MoveGenerator) -> bool {
+        if self.insufficient_material().is_some() {
+            return true;
+        }
+
+        // If the move that reached the 100th halfmove delivered mate, the
+        // game ended by checkmate and not by the 50-move rule. In that
+        // case this is not a draw; the search's mate check takes over.
+        if self.game_state.halfmove_clock >= MAX_MOVE_RULE {
+            let is_checkmate = self.in_check(mg) && self.legal_moves(mg).len() == 0;
+            return !is_checkmate;
+        }
+
+        false
+    }
+
+    // Returns the material difference from the point of view of the side
+    // to move, in centipawns. The middlegame and endgame piece values are
+    // tapered by the game phase. This is much cheaper than a full
+    // evaluation.
+    #[allow(dead_code)]
+    pub fn material_balance(&self) -> i16 {
+        let mg = self.material_difference(&PIECE_VALUES_MG);
+        let eg = self.material_difference(&PIECE_VALUES_EG);
+
+        evaluation::taper(mg, eg, self.phase())
+    }
+
+    // Returns the material difference from the point of view of the side
+    // to move, using the given piece values.
+    fn material_difference(&self, values: &PieceValues) -> i16 {
+        let us = self.us();
+        let opponent = self.opponent();
+        let mut difference = 0;
+
+        for (piece, value) in values.iter().enumerate().take(NrOf::PIECE_TYPES) {
+            let ours = self.bb_pieces[us][piece].count_ones() as i16;
+            let theirs = self.bb_pieces[opponent][piece].count_ones() as i16;
+            difference += (ours - theirs) * value;
+        }
+
+        difference
+    }
+}
+
+// Adjudication decides the outcome of a game from the position alone.
+// This is stricter than the draw detection used by the search: the
+// search also treats positions such as KNN vs. K as a draw, because mate
+// can't be forced. Such a position is not dead, because the opponent
+// could still blunder into mate.
+impl Board {
+    // Returns the adjudication of the current position, if there is one.
+    #[allow(dead_code)]
+    pub fn adjudicate(&self) -> Option<Adjudication> {
+        if self.is_insufficient_material() {
+            Some(Adjudication::DeadPosition)
+        } else {
+            None
+        }
+    }
+
+    // Returns true if neither side can ever checkmate, because the
+    // material on the board is insufficient. These are the dead positions
+    // from the FIDE laws.
+    pub fn is_insufficient_material(&self) -> bool {
+        matches!(
+            self.insufficient_material(),
+            Some(
+                InsufficientMaterial::BareKings
+                    | InsufficientMaterial::SingleMinor
+                    | InsufficientMaterial::SameColorBishops
+            )
+        )
+    }
+
+    // Classifies the material on the board. This is the single place
+    // where insufficient material is recognized; it returns None if at
+    // least one side still has enough material to force mate.
+    pub fn insufficient_material(&self) -> Option<InsufficientMaterial> {
+        let w = &self.bb_pieces[Sides::WHITE];
+        let b = &self.bb_pieces[Sides::BLACK];
+
+        // Any pawn or major piece is enough material.
+        let majors_and_pawns = w[Pieces::QUEEN]
+            | w[Pieces::ROOK]
+            | w[Pieces::PAWN]
+            | b[Pieces::QUEEN]
+            | b[Pieces::ROOK]
+            | b[Pieces::PAWN];
+
+        if majors_and_pawns > 0 {
+            return None;
+        }
+
+        let knights = (w[Pieces::KNIGHT] | b[Pieces::KNIGHT]).count_ones();
+        let bishops = w[Pieces::BISHOP] | b[Pieces::BISHOP];
+        let minors = knights + bishops.count_ones();
+
+        // Bishops on squares of one color can never attack a king
+        // standing on a square of the other color.
+        let same_color_bishops =
+            bishops & BB_DARK_SQUARES == bishops || bishops & BB_DARK_SQUARES == 0;
+
+        match minors {
+            0 => return Some(InsufficientMaterial::BareKings),
+            1 => return Some(InsufficientMaterial::SingleMinor),
+            _ if knights == 0 && same_color_bishops => {
+                return Some(InsufficientMaterial::SameColorBishops)
+            }
+            _ => (),
+        }
+
+        // A side can force mate with two bishops on squares of different
+        // color, or with a bishop and a knight. Otherwise, mate can only
+        // happen if the defending side cooperates.
+        let can_force_mate = |pieces: &[Bitboard; NrOf::PIECE_TYPES]| {
+            let bishops = pieces[Pieces::BISHOP];
+            let two_colors = bishops & BB_DARK_SQUARES > 0 && bishops & !BB_DARK_SQUARES > 0;
+            two_colors || (bishops > 0 && pieces[Pieces::KNIGHT] > 0)
+        };
+
+        if can_force_mate(w) || can_force_mate(b) {
+            None
+        } else {
+            Some(InsufficientMaterial::NoForcedMate)
+        }
+    }
+
+    // Estimates how likely the position is to end in a draw, from the
+    // material configuration: 0 is sharp, Drawishness::MAX is a dead
+    // draw. Bishops on opposite colors, little material and the absence
+    // of passed pawns all make a draw more likely. The evaluation is
+    // scaled toward a draw by this value.
+    pub fn drawishness(&self) -> u8 {
+        match self.insufficient_material() {
+            Some(InsufficientMaterial::NoForcedMate) => return Drawishness::NO_FORCED_MATE,
+            Some(_) => return Drawishness::MAX,
+            None => (),
+        }
+
+        let mut drawishness: u8 = 0;
+
+        if self.opposite_colored_bishops() {
+            let w = &self.bb_pieces[Sides::WHITE];
+            let b = &self.bb_pieces[Sides::BLACK];
+            let others = w[Pieces::QUEEN]
+                | w[Pieces::ROOK]
+                | w[Pieces::KNIGHT]
+                | b[Pieces::QUEEN]
+                | b[Pieces::ROOK]
+                | b[Pieces::KNIGHT];
+
+            drawishness += if others == EMPTY {
+                Drawishness::PURE_OPPOSITE_BISHOPS
+            } else {
+                Drawishness::OPPOSITE_BISHOPS
+            };
+        }
+
+        // The fewer pieces are left, the closer the phase is to 0.
+        drawishness += (MAX_PHASE - self.phase()) as u8;
+
+        let passed = (pawns::passed_pawns(self, Sides::WHITE)
+            | pawns::passed_pawns(self, Sides::BLACK))
+        .count_ones() as u8;
+
+        if passed == 0 {
+            drawishness += Drawishness::NO_PASSED_PAWNS;
+        } else {
+            let penalty = passed.saturating_mul(Drawishness::PER_PASSED_PAWN);
+            drawishness = drawishness.saturating_sub(penalty);
+        }
+
+        drawishness.min(Drawishness::MAX)
+    }
+
+    // Returns true if each side has exactly one bishop, and the bishops
+    // are on squares of different colors.
+    fn opposite_colored_bishops(&self) -> bool {
+        let w = self.bb_pieces[Sides::WHITE][Pieces::BISHOP];
+        let b = self.bb_pieces[Sides::BLACK][Pieces::BISHOP];
+        let one_each = w.count_ones() == 1 && b.count_ones() == 1;
+
+        one_each && ((w & BB_DARK_SQUARES > 0) != (b & BB_DARK_SQUARES > 0))
+    }
+}
+
+// Renders the board as text, with rank 8 on top. White pieces are shown
+// in uppercase and black pieces in lowercase, as in a FEN-string. The
+// state of the game is shown below the board.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rank in RangeOf::RANKS.rev() {
+            write!(f, "{}  ", rank + 1)?;
+            for file in RangeOf::FILES {
+                let square = (rank as usize * NrOf::FILES) + file as usize;
+                let piece = PIECE_CHAR_FEN[self.piece_list[square]];
+                let is_black = self.bb_side[Sides::BLACK] & BB_SQUARES[square] > 0;
+                let c = if is_black {
+                    piece.to_ascii_lowercase()
+                } else {
+                    piece
+                };
+                write!(f, " {c}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "    a b c d e f g h")?;
+        writeln!(f)?;
+
+        let active_color = if self.us() == Sides::WHITE { "w" } else { "b" };
+        let en_passant = match self.game_state.en_passant {
+            Some(ep) => SQUARE_NAME[ep.index()],
+            None => "-",
+        };
+
+        writeln!(f, "Side to move: {active_color}")?;
+        writeln!(
+            f,
+            "Castling: {}",
+            print::castling_as_string(self.game_state.castling)
+        )?;
+        writeln!(f, "En passant: {en_passant}")?;
+        writeln!(f, "Half-move clock: {}", self.game_state.halfmove_clock)?;
+        write!(f, "Full-move number: {}", self.game_state.fullmove_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        board
+    }
+
+    // Returns whether white and black can castle, as [WK, WQ, BK, BQ].
+    fn castling(fen: &str) -> [bool; 4] {
+        let mg = MoveGenerator::new();
+        let board = board(fen);
+        [
+            board.can_castle(&mg, Sides::WHITE, false),
+            board.can_castle(&mg, Sides::WHITE, true),
+            board.can_castle(&mg, Sides::BLACK, false),
+            board.can_castle(&mg, Sides::BLACK, true),
+        ]
+    }
+
+    #[test]
+    fn can_castle_legal() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(castling(fen), [true; 4]);
+
+        // Only the squares the king crosses must be safe, not b1 and b8.
+        let fen = "r3k2r/8/8/1R6/1r6/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(castling(fen), [true; 4]);
+    }
+
+    #[test]
+    fn can_castle_without_rights() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1";
+        assert_eq!(castling(fen), [true, false, false, true]);
+    }
+
+    #[test]
+    fn can_castle_blocked() {
+        let fen = "rn2k1nr/8/8/8/8/8/8/RN2K1NR w KQkq - 0 1";
+        assert_eq!(castling(fen), [false; 4]);
+    }
+
+    #[test]
+    fn can_castle_out_of_check() {
+        let fen = "r3k2r/8/8/8/4r3/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(castling(fen), [false, false, true, true]);
+        let fen = "r3k2r/8/8/4R3/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(castling(fen), [true, true, false, false]);
+    }
+
+    #[test]
+    fn can_castle_through_attacked_square() {
+        let fen = "r3k2r/8/8/8/3r1r2/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(castling(fen), [false, false, true, true]);
+        let fen = "r3k2r/8/8/3R1R2/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(castling(fen), [true, true, false, false]);
+    }
+
+    #[test]
+    fn can_castle_into_check() {
+        let fen = "r3k2r/8/8/8/2r3r1/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(castling(fen), [false, false, true, true]);
+        let fen = "r3k2r/8/8/2R3R1/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(castling(fen), [true, true, false, false]);
+    }
+
+    const KK: &str = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+    const KBK: &str = "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1";
+    const KNK: &str = "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1";
+    const KBKB_SAME: &str = "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1";
+    const KBKB_OPPOSITE: &str = "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1";
+    const KNNK: &str = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+    const KPK: &str = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+
+    const KBBK_SAME: &str = "4k3/8/8/8/8/B7/8/2B1K3 w - - 0 1";
+    const KBBK: &str = "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1";
+    const KBNK: &str = "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1";
+
+    #[test]
+    fn insufficient_material_categories() {
+        let category = |fen| board(fen).insufficient_material();
+        assert_eq!(category(KK), Some(InsufficientMaterial::BareKings));
+        assert_eq!(category(KBK), Some(InsufficientMaterial::SingleMinor));
+        assert_eq!(category(KNK), Some(InsufficientMaterial::SingleMinor));
+        assert_eq!(
+            category(KBKB_SAME),
+            Some(InsufficientMaterial::SameColorBishops)
+        );
+        assert_eq!(
+            category(KBBK_SAME),
+            Some(InsufficientMaterial::SameColorBishops)
+        );
+        assert_eq!(
+            category(KBKB_OPPOSITE),
+            Some(InsufficientMaterial::NoForcedMate)
+        );
+        assert_eq!(category(KNNK), Some(InsufficientMaterial::NoForcedMate));
+        assert_eq!(category(KBBK), None);
+        assert_eq!(category(KBNK), None);
+        assert_eq!(category(KPK), None);
+    }
+
+    #[test]
+    fn is_insufficient_material() {
+        for fen in [KK, KBK, KNK, KBKB_SAME, KBBK_SAME] {
+            assert!(board(fen).is_insufficient_material(), "{fen}");
+        }
+        for fen in [KBKB_OPPOSITE, KNNK, KBBK, KBNK, KPK] {
+            assert!(!board(fen).is_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn adjudicate_dead_positions() {
+        for fen in [KK, KBK, KNK, KBKB_SAME] {
+            assert_eq!(board(fen).adjudicate(), Some(Adjudication::DeadPosition));
+        }
+    }
+
+    #[test]
+    fn adjudicate_live_positions() {
+        // Mate can't be forced with KNN vs. K, but it is still possible.
+        for fen in [KNNK, KBKB_OPPOSITE, KPK] {
+            assert_eq!(board(fen).adjudicate(), None);
+        }
+    }
+
+    #[test]
+    fn start_position_material_is_balanced() {
+        let board = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(board.material_difference(&PIECE_VALUES_MG), 0);
+        assert_eq!(board.material_difference(&PIECE_VALUES_EG), 0);
+        assert_eq!(board.material_balance(), 0);
+    }
+
+    #[test]
+    fn material_balance_is_tapered() {
+        // Phase 0: only kings and pawns, so the endgame values count.
+        assert_eq!(board(KPK).material_balance(), PIECE_VALUES_EG[Pieces::PAWN]);
+
+        // Full phase: the middlegame values count.
+        let board = board("rnbqkbnr/pppppppp/8/8/8/8/1PPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(board.material_balance(), -PIECE_VALUES_MG[Pieces::PAWN]);
+    }
+
+    #[test]
+    fn material_balance_is_relative_to_side_to_move() {
+        // White is a rook up.
+        let white = board("1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1");
+        let black = board("1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQk - 0 1");
+
+        assert!(white.material_balance() > 0);
+        assert!(black.material_balance() < 0);
+        assert_eq!(white.material_balance(), -black.material_balance());
+    }
+
+    #[test]
+    fn drawishness_of_opposite_bishops() {
+        // Opposite-colored bishops and blocked pawns: no one can win.
+        let opposite = board("4k3/3b4/1p1p1p2/1P1P1P2/8/8/3B4/4K3 w - - 0 1");
+        let same = board("4k3/3b4/1p1p1p2/1P1P1P2/8/8/4B3/4K3 w - - 0 1");
+        let with_rook = board("4k3/3b4/1p1p1p2/1P1P1P2/8/8/3B4/R3K3 w - - 0 1");
+
+        assert!(opposite.drawishness() >= 90, "{}", opposite.drawishness());
+        assert!(same.drawishness() < opposite.drawishness());
+        assert!(with_rook.drawishness() < opposite.drawishness());
+        assert_eq!(board(KK).drawishness(), Drawishness::MAX);
+    }
+
+    #[test]
+    fn drawishness_of_connected_passers() {
+        assert_eq!(board("4k3/8/8/8/8/8/3PP3/4K3 w - - 0 1").drawishness(), 0);
+        assert_eq!(board("r3k3/8/8/8/8/8/3PP3/R3K3 w - - 0 1").drawishness(), 0);
+    }
+
+    #[test]
+    fn display_start_position() {
+        let board = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let expected = "\
+8   r n b q k b n r
+7   p p p p p p p p
+6   . . . . . . . .
+5   . . . . . . . .
+4   . . . . . . . .
+3   . . . . . . . .
+2   P P P P P P P P
+1   R N B Q K B N R
+
+    a b c d e f g h
+
+Side to move: w
+Castling: KQkq
+En passant: -
+Half-move clock: 0
+Full-move number: 1";
+
+        assert_eq!(board.to_string(), expected);
+    }
+
+    #[test]
+    fn display_game_state() {
+        let board = board("4k3/8/8/3pP3/8/8/8/4K3 w - d6 3 40");
+        let text = board.to_string();
+        assert!(text.starts_with("8   . . . . k . . .\n"));
+        assert!(text.contains("\n5   . . . p P . . .\n"));
+        assert!(
+            text.ends_with("Castling: -\nEn passant: d6\nHalf-move clock: 3\nFull-move number: 40")
+        );
+    }
+
+    // Checks gives_check() against making each legal move and looking
+    // for an attack on the opponent's king, in every position up to the
+    // given depth.
+    fn compare_gives_check(board: &mut Board, mg: &MoveGenerator, fen: &str, depth: u8) -> usize {
+        let ci = board.check_info(mg);
+        let moves = board.legal_moves(mg);
+        let mut compared = 0;
+
+        for i in 0..moves.len() {
+            let m = moves.get_move(i);
+            let fast = board.gives_check(m, &ci, mg);
+            let us = board.us();
+            assert!(board.make(m, mg));
+            let slow = mg.square_attacked(board, us, board.king_square(board.us()));
+            assert_eq!(fast, slow, "{} in the tree of {fen}", m.as_string());
+            compared += 1;
+            if depth > 1 {
+                compared += compare_gives_check(board, mg, fen, depth - 1);
+            }
+            board.unmake();
+        }
+
+        compared
+    }
+
+    #[test]
+    fn gives_check_agrees_with_make() {
+        let mg = MoveGenerator::new();
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            // Discovered checks by a pawn, a knight and a king; castling
+            // with check; en passant that discovers check.
+            "4k3/8/8/8/4P3/8/4N3/4Q1K1 w - - 0 1",
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            "8/8/8/1k1pP2R/8/8/8/4K3 w - d6 0 1",
+            // Promotions that give check through the square they left.
+            "8/5P2/8/8/5k2/8/8/K7 w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+
+        let mut compared = 0;
+        for fen in fens {
+            let mut board = board(fen);
+            compared += compare_gives_check(&mut board, &mg, fen, 3);
+        }
+        assert!(compared > 100_000, "{compared}");
+    }
+
+    // Calls gives_checkmate() for the legal move written in long algebraic
+    // notation, and checks that the board is left exactly as it was.
+    fn checkmates(fen: &str, uci: &str) -> bool {
+        let mg = MoveGenerator::new();
+        let mut board = board(fen);
+        let moves = board.legal_moves(&mg);
+        let m = (0..moves.len())
+            .map(|i| moves.get_move(i))
+            .find(|m| m.as_string() == uci)
+            .expect("legal move");
+
+        let (before, key, history) = (
+            board.to_string(),
+            board.game_state.zobrist_key,
+            board.history.len(),
+        );
+        let is_mate = board.gives_checkmate(m, &mg);
+        assert_eq!(board.to_string(), before, "{uci}");
+        assert_eq!(board.game_state.zobrist_key, key, "{uci}");
+        assert_eq!(board.history.len(), history, "{uci}");
+
+        is_mate
+    }
+
+    #[test]
+    fn gives_checkmate_only_for_mate() {
+        const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1";
+        const ESCAPE: &str = "6k1/5pp1/8/8/8/8/5PPP/R5K1 w - - 0 1";
+
+        assert!(checkmates(BACK_RANK, "a1a8"));
+        assert!(!checkmates(ESCAPE, "a1a8"));
+        assert!(!checkmates(BACK_RANK, "a1a7"));
+        assert!(!checkmates(BACK_RANK, "g2g3"));
+    }
+
+    #[test]
+    fn legal_captures_are_the_legal_moves_that_capture() {
+        let mg = MoveGenerator::new();
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // En passant, once legal and once exposing the king.
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1",
+            // Promotions, with and without a capture.
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            // In check: only the capture of the checker is a capture.
+            "4k3/8/8/8/8/8/5BPP/4r2K w - - 0 1",
+            // Double check: the king can only capture.
+            "4k3/8/8/8/8/5n2/4r3/4K3 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut board = board(fen);
+            let captures = board.legal_captures(&mg);
+            let moves = board.legal_moves(&mg);
+            let expected: Vec<u32> = (0..moves.len())
+                .map(|i| moves.get_move(i))
+                .filter(|m| {
+                    m.captured() != Pieces::NONE || m.en_passant() || m.promoted() != Pieces::NONE
+                })
+                .map(|m| m.get_move())
+                .collect();
+            let found: Vec<u32> = (0..captures.len())
+                .map(|i| captures.get_move(i).get_move())
+                .collect();
+            assert_eq!(found, expected, "{fen}");
+        }
+
+        // Where en passant is legal, it is included.
+        let mut board = board("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let captures = board.legal_captures(&mg);
+        assert!((0..captures.len()).any(|i| captures.get_move(i).en_passant()));
+    }
+}
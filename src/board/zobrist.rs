@@ -21,6 +21,7 @@ You should have received a copy of the GNU General Public License along
 with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
+use super::defs::Sq;
 use crate::defs::{NrOf, Piece, Side, Sides, Square, EMPTY};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
@@ -91,9 +92,9 @@ impl ZobristRandoms {
         self.rnd_sides[side]
     }
 
-    pub fn en_passant(&self, en_passant: Option<u8>) -> ZobristKey {
+    pub fn en_passant(&self, en_passant: Option<Sq>) -> ZobristKey {
         match en_passant {
-            Some(ep) => self.rnd_en_passant[ep as usize],
+            Some(ep) => self.rnd_en_passant[ep.index()],
             None => self.rnd_en_passant[NrOf::SQUARES],
         }
     }
@@ -51,6 +51,7 @@ pub trait IComm {
     );
     fn send(&self, msg: CommControl);
     fn wait_for_shutdown(&mut self);
+    #[allow(dead_code)]
     fn get_protocol_name(&self) -> &'static str;
 }
 
@@ -30,9 +30,7 @@ use crate::{
     engine::defs::{EngineOption, EngineOptionName, ErrFatal, Information, UiElement},
     misc::print,
     movegen::defs::Move,
-    search::defs::{
-        GameTime, SearchCurrentMove, SearchStats, SearchSummary, CHECKMATE, CHECKMATE_THRESHOLD,
-    },
+    search::defs::{GameTime, ScoreBound, SearchCurrentMove, SearchStats, SearchSummary},
 };
 use crossbeam_channel::{self, Sender};
 use std::{
@@ -55,6 +53,7 @@ pub enum UciReport {
     GoDepth(i8),
     GoMoveTime(u128),
     GoNodes(usize),
+    GoMate(String),
     GoGameTime(GameTime),
     Stop,
     Quit,
@@ -292,6 +291,7 @@ impl Uci {
             Nothing,
             Depth,
             Nodes,
+            Mate,
             MoveTime,
             WTime,
             BTime,
@@ -312,6 +312,7 @@ impl Uci {
                 t if t == "depth" => token = Tokens::Depth,
                 t if t == "movetime" => token = Tokens::MoveTime,
                 t if t == "nodes" => token = Tokens::Nodes,
+                t if t == "mate" => token = Tokens::Mate,
                 t if t == "wtime" => token = Tokens::WTime,
                 t if t == "btime" => token = Tokens::BTime,
                 t if t == "winc" => token = Tokens::WInc,
@@ -334,17 +335,15 @@ impl Uci {
                         report = CommReport::Uci(UciReport::GoNodes(nodes));
                         break; // break for-loop: nothing more to do.
                     }
+                    Tokens::Mate => {
+                        report = CommReport::Uci(UciReport::GoMate(p));
+                        break; // break for-loop: nothing more to do.
+                    }
                     Tokens::WTime => game_time.wtime = p.parse::<u128>().unwrap_or(0),
                     Tokens::BTime => game_time.btime = p.parse::<u128>().unwrap_or(0),
                     Tokens::WInc => game_time.winc = p.parse::<u128>().unwrap_or(0),
                     Tokens::BInc => game_time.binc = p.parse::<u128>().unwrap_or(0),
-                    Tokens::MovesToGo => {
-                        game_time.moves_to_go = if let Ok(x) = p.parse::<usize>() {
-                            Some(x)
-                        } else {
-                            None
-                        }
-                    }
+                    Tokens::MovesToGo => game_time.moves_to_go = p.parse::<usize>().ok(),
                 }, // end match token
             } // end match p
         } // end for
@@ -395,6 +394,10 @@ impl Uci {
             match &name[..] {
                 "hash" => eon = EngineOptionName::Hash(value),
                 "clear hash" => eon = EngineOptionName::ClearHash,
+                "skill level" => eon = EngineOptionName::SkillLevel(value),
+                "uci_analysemode" => eon = EngineOptionName::AnalyseMode(value),
+                "multipv" => eon = EngineOptionName::MultiPv(value),
+                "minthinktime" => eon = EngineOptionName::MinThinkTime(value),
                 _ => (),
             }
         }
@@ -418,6 +421,7 @@ impl Uci {
             let ui_element = match o.ui_element {
                 UiElement::Spin => String::from("type spin"),
                 UiElement::Button => String::from("type button"),
+                UiElement::Check => String::from("type check"),
             };
 
             let value_default = if let Some(v) = &o.default {
@@ -454,28 +458,30 @@ impl Uci {
         println!("readyok");
     }
 
-    fn search_summary(s: &SearchSummary) {
+    // Returns the score of the summary as it is sent to the GUI: a mate
+    // in moves if a mate was found, or the score in centipawns, followed
+    // by the bound if the score is only a bound.
+    fn score(s: &SearchSummary) -> String {
         // If mate found, report this; otherwise report normal score.
-        let score = if (s.cp.abs() >= CHECKMATE_THRESHOLD) && (s.cp.abs() < CHECKMATE) {
-            // Number of plies to mate.
-            let ply = CHECKMATE - s.cp.abs();
-
-            // Check if the number of ply's is odd
-            let is_odd = ply % 2 == 1;
-
-            // Calculate number of moves to mate
-            let moves = if is_odd { (ply + 1) / 2 } else { ply / 2 };
-
-            // If the engine is being mated itself, flip the score.
-            let flip = if s.cp < 0 { -1 } else { 1 };
-
-            // Report the mate
-            format!("mate {}", moves * flip)
+        let score = if s.mate != 0 {
+            format!("mate {}", s.mate)
         } else {
-            // Report the normal score if there's no mate detected.
             format!("cp {}", s.cp)
         };
 
+        // Tell the GUI if the score is only a bound.
+        let bound = match s.bound {
+            ScoreBound::Exact => "",
+            ScoreBound::Lower => " lowerbound",
+            ScoreBound::Upper => " upperbound",
+        };
+
+        format!("score {score}{bound}")
+    }
+
+    fn search_summary(s: &SearchSummary) {
+        let score = Uci::score(s);
+
         // Report depth and seldepth (if available).
         let depth = if s.seldepth > 0 {
             format!("depth {} seldepth {}", s.depth, s.seldepth)
@@ -490,11 +496,18 @@ impl Uci {
             String::from(" ")
         };
 
+        // Only display the line number if several lines are searched.
+        let multi_pv = if let Some(line) = s.multi_pv {
+            format!("multipv {line} ")
+        } else {
+            String::from("")
+        };
+
         let pv = s.pv_as_string();
 
         let info = format!(
-            "info score {} {} time {} nodes {} nps {}{}pv {}",
-            score, depth, s.time, s.nodes, s.nps, hash_full, pv,
+            "info {}{} {} time {} nodes {} nps {} tbhits {}{}pv {}",
+            multi_pv, score, depth, s.time, s.nodes, s.nps, s.tb_hits, hash_full, pv,
         );
 
         println!("{info}");
@@ -516,8 +529,8 @@ impl Uci {
         };
 
         println!(
-            "info time {} nodes {} nps {}{}",
-            s.time, s.nodes, s.nps, hash_full
+            "info time {} nodes {} nps {} tbhits {}{}",
+            s.time, s.nodes, s.nps, s.tb_hits, hash_full
         );
     }
 
@@ -525,8 +538,14 @@ impl Uci {
         println!("info string {msg}");
     }
 
+    // If there is no move to play, because the game is over, UCI expects
+    // the null move "0000".
     fn best_move(m: &Move) {
-        println!("bestmove {}", m.as_string());
+        if m.get_move() == 0 {
+            println!("bestmove 0000");
+        } else {
+            println!("bestmove {}", m.as_string());
+        }
     }
 }
 
@@ -568,3 +587,47 @@ impl Uci {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{defs::CHECKMATE, Search};
+
+    // Returns the score as it would be sent to the GUI.
+    fn score(score: i16, bound: ScoreBound) -> String {
+        let summary = SearchSummary {
+            depth: 1,
+            seldepth: 0,
+            time: 0,
+            cp: score,
+            mate: Search::moves_to_mate(score),
+            nodes: 0,
+            nps: 0,
+            tb_hits: 0,
+            hash_full: 0,
+            pv: Vec::new(),
+            multi_pv: None,
+            bound,
+        };
+        Uci::score(&summary)
+    }
+
+    #[test]
+    fn score_strings() {
+        // Mate in 3 is 5 plies; being mated in 2 is 4 plies.
+        assert_eq!(score(CHECKMATE - 5, ScoreBound::Exact), "score mate 3");
+        assert_eq!(score(-(CHECKMATE - 4), ScoreBound::Exact), "score mate -2");
+        assert_eq!(score(35, ScoreBound::Exact), "score cp 35");
+        assert_eq!(score(-120, ScoreBound::Upper), "score cp -120 upperbound");
+        assert_eq!(score(60, ScoreBound::Lower), "score cp 60 lowerbound");
+    }
+
+    #[test]
+    fn analyse_mode_option_is_parsed() {
+        let report = Uci::parse_setoption("setoption name UCI_AnalyseMode value true");
+        assert!(matches!(
+            report,
+            CommReport::Uci(UciReport::SetOption(EngineOptionName::AnalyseMode(v))) if v == "true"
+        ));
+    }
+}
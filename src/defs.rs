@@ -71,7 +71,7 @@ impl Castling {
 
 pub const EMPTY: u64 = 0;
 pub const MAX_GAME_MOVES: usize = 2048;
-pub const MAX_LEGAL_MOVES: u8 = 255;
+pub const MAX_LEGAL_MOVES: u8 = 255; // Move list capacity; the known maximum is 218
 pub const MAX_PLY: i8 = 125;
 pub const MAX_MOVE_RULE: u8 = 100; // 50/75 move rule
 
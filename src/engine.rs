@@ -48,7 +48,7 @@ use transposition::{PerftData, SearchData, TT};
 #[cfg(feature = "extra")]
 use crate::{
     board::defs::Pieces,
-    extra::{testsuite, wizardry},
+    extra::{legalcheck, tactics, testsuite, ttbench, wizardry},
 };
 
 // This struct holds the chess engine and its functions, so they are not
@@ -114,6 +114,34 @@ impl Engine {
                 None,
                 None,
             ),
+            EngineOption::new(
+                EngineOptionName::SKILL_LEVEL,
+                UiElement::Spin,
+                Some(EngineOptionDefaults::SKILL_LEVEL_DEFAULT.to_string()),
+                Some(EngineOptionDefaults::SKILL_LEVEL_MIN.to_string()),
+                Some(EngineOptionDefaults::SKILL_LEVEL_MAX.to_string()),
+            ),
+            EngineOption::new(
+                EngineOptionName::ANALYSE_MODE,
+                UiElement::Check,
+                Some(EngineOptionDefaults::ANALYSE_MODE_DEFAULT.to_string()),
+                None,
+                None,
+            ),
+            EngineOption::new(
+                EngineOptionName::MULTI_PV,
+                UiElement::Spin,
+                Some(EngineOptionDefaults::MULTI_PV_DEFAULT.to_string()),
+                Some(EngineOptionDefaults::MULTI_PV_MIN.to_string()),
+                Some(EngineOptionDefaults::MULTI_PV_MAX.to_string()),
+            ),
+            EngineOption::new(
+                EngineOptionName::MIN_THINK_TIME,
+                UiElement::Spin,
+                Some(EngineOptionDefaults::MIN_THINK_TIME_DEFAULT.to_string()),
+                Some(EngineOptionDefaults::MIN_THINK_TIME_MIN.to_string()),
+                Some(EngineOptionDefaults::MIN_THINK_TIME_MAX.to_string()),
+            ),
         ];
 
         // Initialize correct TT.
@@ -134,6 +162,10 @@ impl Engine {
                 threads,
                 quiet,
                 tt_size,
+                skill_level: EngineOptionDefaults::SKILL_LEVEL_DEFAULT,
+                analyse_mode: EngineOptionDefaults::ANALYSE_MODE_DEFAULT,
+                multi_pv: EngineOptionDefaults::MULTI_PV_DEFAULT,
+                min_think_time: EngineOptionDefaults::MIN_THINK_TIME_DEFAULT,
             },
             options: Arc::new(options),
             cmdline,
@@ -203,6 +235,31 @@ impl Engine {
             self.tt_search.lock().expect(ErrFatal::LOCK).resize(0);
             testsuite::run(Arc::clone(&self.tt_perft), self.settings.tt_size > 0);
         }
+
+        #[cfg(feature = "extra")]
+        // Benchmark the search TT's insert and probe speed if requested.
+        // If the TT is too slow, exit with an error code, so a script
+        // running the benchmark can detect it.
+        if self.cmdline.has_tt_bench() {
+            action_requested = true;
+            if !ttbench::run(self.settings.tt_size) {
+                std::process::exit(1);
+            }
+        }
+
+        #[cfg(feature = "extra")]
+        // Run the tactics test suite if requested.
+        if self.cmdline.has_tactics() {
+            action_requested = true;
+            tactics::run(self.settings.tt_size);
+        }
+
+        #[cfg(feature = "extra")]
+        // Cross-check the legal move counts if requested.
+        if self.cmdline.has_legal_check() {
+            action_requested = true;
+            legalcheck::run();
+        }
         // =====================================================
 
         // In the main loop, the engine manages its resources so it will be
@@ -26,10 +26,11 @@ use super::{
     Engine,
 };
 use crate::{
+    board::defs::MoveError,
     comm::{uci::UciReport, CommControl, CommReport},
-    defs::FEN_START_POSITION,
-    engine::defs::EngineOptionName,
-    evaluation::evaluate_position,
+    defs::{ENGINE_RUN_ERRORS, FEN_START_POSITION},
+    engine::defs::{EngineOptionDefaults, EngineOptionName},
+    evaluation::{evaluate_position, pawns::PawnHash},
     search::defs::{SearchControl, SearchMode, SearchParams, OVERHEAD},
 };
 
@@ -48,6 +49,10 @@ impl Engine {
         // Setup default variables.
         let mut sp = SearchParams::new();
         sp.quiet = self.settings.quiet;
+        sp.skill_level = self.settings.skill_level;
+        sp.analyse_mode = self.settings.analyse_mode;
+        sp.multi_pv = self.settings.multi_pv;
+        sp.min_think_time = self.settings.min_think_time;
 
         match u {
             UciReport::Uci => self.comm.send(CommControl::Identify),
@@ -78,6 +83,47 @@ impl Engine {
                         self.tt_search.lock().expect(ErrFatal::LOCK).clear()
                     }
 
+                    EngineOptionName::SkillLevel(value) => {
+                        if let Ok(v) = value.parse::<u8>() {
+                            self.settings.skill_level =
+                                v.min(EngineOptionDefaults::SKILL_LEVEL_MAX);
+                        } else {
+                            let msg = String::from(ErrNormal::NOT_INT);
+                            self.comm.send(CommControl::InfoString(msg));
+                        }
+                    }
+
+                    EngineOptionName::AnalyseMode(value) => {
+                        if let Ok(v) = value.parse::<bool>() {
+                            self.settings.analyse_mode = v;
+                        } else {
+                            let msg = String::from(ErrNormal::NOT_BOOL);
+                            self.comm.send(CommControl::InfoString(msg));
+                        }
+                    }
+
+                    EngineOptionName::MultiPv(value) => {
+                        if let Ok(v) = value.parse::<u8>() {
+                            self.settings.multi_pv = v.clamp(
+                                EngineOptionDefaults::MULTI_PV_MIN,
+                                EngineOptionDefaults::MULTI_PV_MAX,
+                            );
+                        } else {
+                            let msg = String::from(ErrNormal::NOT_INT);
+                            self.comm.send(CommControl::InfoString(msg));
+                        }
+                    }
+
+                    EngineOptionName::MinThinkTime(value) => {
+                        if let Ok(v) = value.parse::<u128>() {
+                            self.settings.min_think_time =
+                                v.min(EngineOptionDefaults::MIN_THINK_TIME_MAX);
+                        } else {
+                            let msg = String::from(ErrNormal::NOT_INT);
+                            self.comm.send(CommControl::InfoString(msg));
+                        }
+                    }
+
                     EngineOptionName::Nothing => (),
                 };
             }
@@ -87,17 +133,22 @@ impl Engine {
 
                 if fen_result.is_ok() {
                     for m in moves.iter() {
-                        let ok = self.execute_move(m.clone());
-                        if !ok {
-                            let msg = format!("{}: {}", m, ErrNormal::NOT_LEGAL);
+                        if let Err(e) = self.execute_move(m.clone()) {
+                            let reason = match e {
+                                MoveError::NotAMove => ErrNormal::NOT_A_MOVE,
+                                MoveError::NotPseudoLegal => ErrNormal::NOT_LEGAL,
+                                MoveError::LeavesKingInCheck => ErrNormal::KING_IN_CHECK,
+                            };
+                            let msg = format!("{m}: {reason}");
                             self.comm.send(CommControl::InfoString(msg));
                             break;
                         }
                     }
                 }
 
-                if fen_result.is_err() {
-                    let msg = ErrNormal::FEN_FAILED.to_string();
+                if let Err(e) = fen_result {
+                    let reason = ENGINE_RUN_ERRORS[u8::from(e) as usize];
+                    let msg = format!("{} {reason}", ErrNormal::FEN_FAILED);
                     self.comm.send(CommControl::InfoString(msg));
                 }
             }
@@ -125,6 +176,18 @@ impl Engine {
                 self.search.send(SearchControl::Start(sp));
             }
 
+            UciReport::GoMate(value) => match value.parse::<u8>() {
+                Ok(moves) if moves > 0 => {
+                    sp.mate = moves;
+                    sp.search_mode = SearchMode::Mate;
+                    self.search.send(SearchControl::Start(sp));
+                }
+                _ => {
+                    let msg = String::from(ErrNormal::NOT_MATE_IN);
+                    self.comm.send(CommControl::InfoString(msg));
+                }
+            },
+
             UciReport::GoGameTime(gt) => {
                 sp.game_time = *gt;
                 sp.search_mode = SearchMode::GameTime;
@@ -138,7 +201,9 @@ impl Engine {
             UciReport::Board => self.comm.send(CommControl::PrintBoard),
             UciReport::History => self.comm.send(CommControl::PrintHistory),
             UciReport::Eval => {
-                let e = evaluate_position(&self.board.lock().expect(ErrFatal::LOCK));
+                let mut pawn_hash = PawnHash::new();
+                let board = self.board.lock().expect(ErrFatal::LOCK);
+                let e = evaluate_position(&board, &mut pawn_hash);
                 let msg = format!("Evaluation: {e} centipawns");
                 self.comm.send(CommControl::InfoString(msg));
             }
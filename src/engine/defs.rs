@@ -21,8 +21,11 @@ You should have received a copy of the GNU General Public License along
 with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
-pub use crate::engine::transposition::{HashFlag, PerftData, SearchData, TT};
-use crate::{comm::CommReport, search::defs::SearchReport};
+pub use crate::engine::transposition::{AnalysisData, HashFlag, PerftData, SearchData, TT};
+use crate::{
+    comm::CommReport,
+    search::defs::{SearchReport, MAX_SKILL_LEVEL},
+};
 
 // This struct holds messages that are reported on fatal engine errors.
 // These should never happen; if they do the engine is in an unknown state,
@@ -41,8 +44,12 @@ impl ErrFatal {
 
 pub struct ErrNormal;
 impl ErrNormal {
+    pub const NOT_A_MOVE: &'static str = "This is not a move in long algebraic notation.";
     pub const NOT_LEGAL: &'static str = "This is not a legal move in this position.";
+    pub const KING_IN_CHECK: &'static str = "This move would leave the king in check.";
     pub const NOT_INT: &'static str = "The value given was not an integer.";
+    pub const NOT_BOOL: &'static str = "The value given was not 'true' or 'false'.";
+    pub const NOT_MATE_IN: &'static str = "The number of moves to mate must be from 1 to 255.";
     pub const FEN_FAILED: &'static str = "Setting up FEN failed. Board not changed.";
 }
 
@@ -51,6 +58,10 @@ pub struct Settings {
     pub threads: usize,
     pub quiet: bool,
     pub tt_size: usize,
+    pub skill_level: u8,
+    pub analyse_mode: bool,
+    pub multi_pv: u8,
+    pub min_think_time: u128,
 }
 
 // This enum provides informatin to the engine, with regard to incoming
@@ -64,6 +75,7 @@ pub enum Information {
 pub enum UiElement {
     Spin,
     Button,
+    Check,
 }
 
 pub struct EngineOption {
@@ -96,11 +108,19 @@ impl EngineOption {
 pub enum EngineOptionName {
     Hash(String),
     ClearHash,
+    SkillLevel(String),
+    AnalyseMode(String),
+    MultiPv(String),
+    MinThinkTime(String),
     Nothing,
 }
 impl EngineOptionName {
     pub const HASH: &'static str = "Hash";
     pub const CLEAR_HASH: &'static str = "Clear Hash";
+    pub const SKILL_LEVEL: &'static str = "Skill Level";
+    pub const ANALYSE_MODE: &'static str = "UCI_AnalyseMode";
+    pub const MULTI_PV: &'static str = "MultiPV";
+    pub const MIN_THINK_TIME: &'static str = "MinThinkTime";
 }
 
 pub struct EngineOptionDefaults;
@@ -109,4 +129,14 @@ impl EngineOptionDefaults {
     pub const HASH_MIN: usize = 0;
     pub const HASH_MAX_64_BIT: usize = 65536;
     pub const HASH_MAX_32_BIT: usize = 2048;
+    pub const SKILL_LEVEL_DEFAULT: u8 = MAX_SKILL_LEVEL;
+    pub const SKILL_LEVEL_MIN: u8 = 0;
+    pub const SKILL_LEVEL_MAX: u8 = MAX_SKILL_LEVEL;
+    pub const ANALYSE_MODE_DEFAULT: bool = false;
+    pub const MULTI_PV_DEFAULT: u8 = 1;
+    pub const MULTI_PV_MIN: u8 = 1;
+    pub const MULTI_PV_MAX: u8 = 16;
+    pub const MIN_THINK_TIME_DEFAULT: u128 = 0;
+    pub const MIN_THINK_TIME_MIN: u128 = 0;
+    pub const MIN_THINK_TIME_MAX: u128 = 10_000;
 }
@@ -28,6 +28,7 @@ const ENTRIES_PER_BUCKET: usize = 4;
 const HIGH_FOUR_BYTES: u64 = 0xFF_FF_FF_FF_00_00_00_00;
 const LOW_FOUR_BYTES: u64 = 0x00_00_00_00_FF_FF_FF_FF;
 const SHIFT_TO_LOWER: u64 = 32;
+const VERIFICATION_BITS: i32 = 32;
 
 /* ===== Data ========================================================= */
 
@@ -77,31 +78,49 @@ pub enum HashFlag {
     Beta,
 }
 
+// SearchData is packed into 8 bytes. The depth, the flag and the best
+// move share one u32: the move takes the low 24 bits, the flag the next
+// two, and the depth the top six. A depth above 63 is stored as 63; that
+// is never reached in practice, and a shallower depth is always safe to
+// store. A position without a static evaluation (the side to move was in
+// check) stores NO_STATIC_EVAL, which no evaluation can reach.
+const MOVE_BITS: u32 = 0x00_FF_FF_FF;
+const FLAG_SHIFT: u32 = 24;
+const FLAG_BITS: u32 = 0b11;
+const DEPTH_SHIFT: u32 = 26;
+const MAX_STORED_DEPTH: i8 = 63;
+const NO_STATIC_EVAL: i16 = i16::MIN;
+
 #[derive(Copy, Clone)]
 pub struct SearchData {
-    depth: i8,
-    flag: HashFlag,
+    data: u32,
     value: i16,
-    best_move: ShortMove,
+    static_eval: i16,
 }
 
 impl IHashData for SearchData {
     fn new() -> Self {
         Self {
-            depth: 0,
-            flag: HashFlag::Nothing,
+            data: 0,
             value: 0,
-            best_move: ShortMove::new(0),
+            static_eval: NO_STATIC_EVAL,
         }
     }
 
     fn depth(&self) -> i8 {
-        self.depth
+        (self.data >> DEPTH_SHIFT) as i8
     }
 }
 
 impl SearchData {
-    pub fn create(depth: i8, ply: i8, flag: HashFlag, value: i16, best_move: ShortMove) -> Self {
+    pub fn create(
+        depth: i8,
+        ply: i8,
+        flag: HashFlag,
+        value: i16,
+        static_eval: Option<i16>,
+        best_move: ShortMove,
+    ) -> Self {
         // This is the value we're going to save into the TT.
         let mut v = value;
 
@@ -114,24 +133,60 @@ impl SearchData {
             v += ply as i16;
         }
 
-        if v < CHECKMATE_THRESHOLD {
+        if v < -CHECKMATE_THRESHOLD {
             v -= ply as i16;
         }
 
+        let depth = depth.clamp(0, MAX_STORED_DEPTH) as u32;
+        let data = (depth << DEPTH_SHIFT)
+            | ((flag as u32) << FLAG_SHIFT)
+            | (best_move.get_move() & MOVE_BITS);
+
         Self {
-            depth,
-            flag,
+            data,
             value: v,
-            best_move,
+            static_eval: static_eval.unwrap_or(NO_STATIC_EVAL),
+        }
+    }
+
+    pub fn flag(&self) -> HashFlag {
+        match (self.data >> FLAG_SHIFT) & FLAG_BITS {
+            1 => HashFlag::Exact,
+            2 => HashFlag::Alpha,
+            3 => HashFlag::Beta,
+            _ => HashFlag::Nothing,
+        }
+    }
+
+    fn best_move(&self) -> ShortMove {
+        ShortMove::new(self.data & MOVE_BITS)
+    }
+
+    // The static evaluation of the position, so it doesn't have to be
+    // computed again when the position is searched again. There is none
+    // if the side to move was in check.
+    pub fn static_eval(&self) -> Option<i16> {
+        if self.static_eval == NO_STATIC_EVAL {
+            None
+        } else {
+            Some(self.static_eval)
         }
     }
 
+    // Returns true if the entry is too shallow for a cutoff at the given
+    // depth, but comes from a search at most 'max_gap' plies shallower
+    // that failed high against the given beta.
+    pub fn shallow_fail_high(&self, depth: i8, beta: i16, max_gap: i8) -> bool {
+        let shallow = self.depth() < depth && self.depth() >= depth - max_gap;
+        shallow && matches!(self.flag(), HashFlag::Beta) && self.value >= beta
+    }
+
     pub fn get(&self, depth: i8, ply: i8, alpha: i16, beta: i16) -> (Option<i16>, ShortMove) {
         // We either do, or don't have a value to return from the TT.
         let mut value: Option<i16> = None;
 
-        if self.depth >= depth {
-            match self.flag {
+        if self.depth() >= depth {
+            match self.flag() {
                 HashFlag::Exact => {
                     // Get the value from the data. We don't want to change
                     // the value that is in the TT.
@@ -144,27 +199,93 @@ impl SearchData {
                         v -= ply as i16;
                     }
 
-                    if v < CHECKMATE_THRESHOLD {
+                    if v < -CHECKMATE_THRESHOLD {
                         v += ply as i16;
                     }
 
                     // This is the value that will be returned.
                     value = Some(v);
                 }
-                HashFlag::Alpha => {
-                    if self.value <= alpha {
-                        value = Some(alpha);
-                    }
-                }
-                HashFlag::Beta => {
-                    if self.value >= beta {
-                        value = Some(beta);
-                    }
-                }
+                HashFlag::Alpha if self.value <= alpha => value = Some(alpha),
+                HashFlag::Beta if self.value >= beta => value = Some(beta),
                 _ => (),
             };
         }
-        (value, self.best_move)
+        (value, self.best_move())
+    }
+}
+
+// In analysis mode, the search also keeps an annotation of each position
+// it completes: the score and best move, next to the static evaluation and
+// the number of nodes that were searched below the position. This can be
+// used to build an annotated tree of the analysis. The flag tells if the
+// value is exact or a bound, as in SearchData. Mate values are stored as
+// they were found, counted from the root of the analysis.
+#[derive(Copy, Clone)]
+pub struct AnalysisData {
+    depth: i8,
+    flag: HashFlag,
+    value: i16,
+    best_move: ShortMove,
+    static_eval: Option<i16>,
+    nodes: usize,
+}
+
+impl IHashData for AnalysisData {
+    fn new() -> Self {
+        Self {
+            depth: 0,
+            flag: HashFlag::Nothing,
+            value: 0,
+            best_move: ShortMove::new(0),
+            static_eval: None,
+            nodes: 0,
+        }
+    }
+
+    fn depth(&self) -> i8 {
+        self.depth
+    }
+}
+
+#[allow(dead_code)]
+impl AnalysisData {
+    pub fn create(
+        depth: i8,
+        flag: HashFlag,
+        value: i16,
+        best_move: ShortMove,
+        static_eval: Option<i16>,
+        nodes: usize,
+    ) -> Self {
+        Self {
+            depth,
+            flag,
+            value,
+            best_move,
+            static_eval,
+            nodes,
+        }
+    }
+
+    pub fn flag(&self) -> HashFlag {
+        self.flag
+    }
+
+    pub fn value(&self) -> i16 {
+        self.value
+    }
+
+    pub fn best_move(&self) -> ShortMove {
+        self.best_move
+    }
+
+    pub fn static_eval(&self) -> Option<i16> {
+        self.static_eval
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.nodes
     }
 }
 
@@ -199,26 +320,33 @@ impl<D: IHashData + Copy> Bucket<D> {
         }
     }
 
-    // Store a position in the bucket. Replace the position with the stored
-    // lowest depth, as positions with higher depth are more valuable.
+    // Store a position in the bucket. If the position is already in the
+    // bucket, its entry is updated, so a position is never stored twice.
+    // Otherwise an empty entry is used if there is one. If the bucket is
+    // full, replace the position with the lowest stored depth, as
+    // positions with higher depth are more valuable.
     pub fn store(&mut self, verification: u32, data: D, used_entries: &mut usize) {
-        let mut idx_lowest_depth = 0;
-
-        // Find the index of the entry with the lowest depth.
-        for entry in 1..ENTRIES_PER_BUCKET {
-            if self.bucket[entry].data.depth() < data.depth() {
-                idx_lowest_depth = entry
-            }
-        }
-
-        // If the verifiaction was 0, this entry in the bucket was never
-        // used before. Count the use of this entry.
-        if self.bucket[idx_lowest_depth].verification == 0 {
+        let idx = if let Some(i) = self.position_of(verification) {
+            i
+        } else if let Some(i) = self.position_of(0) {
+            i
+        } else {
+            self.index_of_lowest_depth()
+        };
+
+        // An entry with verification 0 was never used before. Keep the
+        // count of used entries equal to the number of entries with a
+        // verification that is not 0, whatever this store replaces.
+        let was_used = self.bucket[idx].verification != 0;
+        let is_used = verification != 0;
+        if is_used && !was_used {
             *used_entries += 1;
+        } else if was_used && !is_used {
+            *used_entries -= 1;
         }
 
         // Store.
-        self.bucket[idx_lowest_depth] = Entry { verification, data }
+        self.bucket[idx] = Entry { verification, data }
     }
 
     // Find a position in the bucket, where both the stored verification and
@@ -231,8 +359,38 @@ impl<D: IHashData + Copy> Bucket<D> {
         }
         None
     }
+
+    // Find the index of the entry with the given verification.
+    fn position_of(&self, verification: u32) -> Option<usize> {
+        self.bucket
+            .iter()
+            .position(|e| e.verification == verification)
+    }
+
+    // Find the index of the entry with the lowest depth.
+    fn index_of_lowest_depth(&self) -> usize {
+        let mut idx_lowest_depth = 0;
+
+        for entry in 1..ENTRIES_PER_BUCKET {
+            let lowest_depth = self.bucket[idx_lowest_depth].data.depth();
+            if self.bucket[entry].data.depth() < lowest_depth {
+                idx_lowest_depth = entry
+            }
+        }
+
+        idx_lowest_depth
+    }
 }
 
+// The memory used by the TT scales with the size of the search data: an
+// entry holds a verification next to the data, and a bucket holds
+// ENTRIES_PER_BUCKET entries. These sizes are checked at compile time, so
+// a new field that makes them grow doesn't go unnoticed. If one does,
+// pack the fields more tightly, or raise the limits on purpose.
+const _: () = assert!(std::mem::size_of::<SearchData>() <= 8);
+const _: () = assert!(std::mem::size_of::<Entry<SearchData>>() <= 12);
+const _: () = assert!(std::mem::size_of::<Bucket<SearchData>>() <= 48);
+
 /* ===== TT =================================================== */
 
 // Transposition Table
@@ -253,7 +411,7 @@ impl<D: IHashData + Copy + Clone> TT<D> {
         Self {
             tt: vec![Bucket::<D>::new(); total_buckets],
             used_entries: 0,
-            total_buckets
+            total_buckets,
         }
     }
 
@@ -271,6 +429,7 @@ impl<D: IHashData + Copy + Clone> TT<D> {
         self.tt = vec![Bucket::<D>::new(); buckets];
         self.used_entries = 0;
         self.total_buckets = buckets;
+        self.validate();
     }
 
     // Insert a position at the calculated index, by storing it in the
@@ -301,23 +460,76 @@ impl<D: IHashData + Copy + Clone> TT<D> {
         self.resize_to_bucket_count(self.total_buckets);
     }
 
+    // Checks the internal invariants of the TT, and panics if one of them
+    // is broken. This only runs in debug mode; in release mode, this
+    // function does nothing.
+    pub fn validate(&self) {
+        if cfg!(debug_assertions) {
+            let capacity = self.total_buckets * ENTRIES_PER_BUCKET;
+            let mut in_use = 0;
+
+            assert_eq!(self.tt.len(), self.total_buckets, "TT: wrong bucket count");
+
+            for bucket in self.tt.iter() {
+                let mut seen: Vec<u32> = Vec::with_capacity(ENTRIES_PER_BUCKET);
+                for e in bucket.bucket.iter().filter(|e| e.verification != 0) {
+                    assert!(!seen.contains(&e.verification), "TT: position stored twice");
+                    seen.push(e.verification);
+                    in_use += 1;
+                }
+            }
+
+            assert_eq!(self.used_entries, in_use, "TT: used entries miscounted");
+            assert!(
+                self.used_entries <= capacity,
+                "TT: more entries used than exist"
+            );
+        }
+    }
+
     // Provides TT usage in permille (1 per 1000, as oppposed to percent,
     // which is 1 per 100.)
     pub fn hash_full(&self) -> u16 {
         if self.total_buckets > 0 {
-            ((self.used_entries as f64 / (self.total_buckets * ENTRIES_PER_BUCKET) as f64) * 1000f64).floor() as u16
+            ((self.used_entries as f64 / (self.total_buckets * ENTRIES_PER_BUCKET) as f64)
+                * 1000f64)
+                .floor() as u16
         } else {
             0
         }
     }
+
+    // Estimates the chance that a probe for a position that is not in the
+    // TT returns the data of another position. The bucket is found with
+    // the upper half of the Zobrist key. Each used entry in it then
+    // matches the 32-bit verification by accident with a chance of
+    // p = 1 / 2^32. With k used entries per bucket on average, the chance
+    // of at least one false match is 1 - (1 - p)^k, which is about k * p.
+    // A fuller TT has more entries per bucket, and thus more collisions.
+    #[allow(dead_code)]
+    pub fn estimated_collision_probability(&self) -> f64 {
+        if self.total_buckets == 0 {
+            return 0.0;
+        }
+
+        let k = self.used_entries as f64 / self.total_buckets as f64;
+        let p = 2f64.powi(-VERIFICATION_BITS);
+
+        // Computed as -(e^(k * ln(1 - p)) - 1), because 1 - (1 - p)^k
+        // loses most of its precision when p is this small.
+        -(k * (-p).ln_1p()).exp_m1()
+    }
 }
 
 // Private functions
 impl<D: IHashData + Copy + Clone> TT<D> {
     // Calculate the index (bucket) where the data is going to be stored.
     // Use only the upper half of the Zobrist key for this, so the lower
-    // half can be used to calculate a verification.
+    // half can be used to calculate a verification. The TT must not be
+    // empty; a TT of 0 MB has no index to return, so callers have to check
+    // total_buckets first.
     fn calculate_index(&self, zobrist_key: ZobristKey) -> usize {
+        debug_assert!(self.total_buckets > 0, "TT: index requested in empty table");
         let key = (zobrist_key & HIGH_FOUR_BYTES) >> SHIFT_TO_LOWER;
         let total = self.total_buckets as u64;
 
@@ -334,8 +546,262 @@ impl<D: IHashData + Copy + Clone> TT<D> {
     // This function calculates the value for total_buckets depending on the
     // requested TT size.
     fn calculate_init_buckets(megabytes: usize) -> usize {
-        const BUCKET_SIZE: usize = size_of::<Bucket<D>>();
-        const BUCKETS_PER_MB: usize = MEGABYTE / BUCKET_SIZE;
-        megabytes * BUCKETS_PER_MB
+        let bucket_size = std::mem::size_of::<Bucket<D>>();
+        let buckets_per_mb = MEGABYTE / bucket_size;
+        megabytes * buckets_per_mb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::defs::{CHECKMATE, INF};
+
+    const KEY: ZobristKey = 0x0123_4567_89AB_CDEF;
+
+    // Stores the value at the given ply and probes it back at another.
+    fn round_trip(value: i16, store_ply: i8, probe_ply: i8) -> Option<i16> {
+        let mut tt: TT<SearchData> = TT::new(1);
+        let data = SearchData::create(
+            5,
+            store_ply,
+            HashFlag::Exact,
+            value,
+            None,
+            ShortMove::new(0),
+        );
+        tt.insert(KEY, data);
+
+        tt.probe(KEY).unwrap().get(5, probe_ply, -INF, INF).0
+    }
+
+    #[test]
+    fn normal_values_are_not_adjusted() {
+        for value in [
+            -CHECKMATE_THRESHOLD,
+            -300,
+            -1,
+            0,
+            1,
+            300,
+            CHECKMATE_THRESHOLD,
+        ] {
+            assert_eq!(round_trip(value, 4, 9), Some(value));
+        }
+    }
+
+    #[test]
+    fn mate_values_are_adjusted_for_ply() {
+        // A score of CHECKMATE - 14 at ply 4 is a mate 10 plies below that
+        // node. Probed at ply 9, the position is still 10 plies from mate,
+        // so the score becomes CHECKMATE - 19.
+        let winning = CHECKMATE - 14;
+        let losing = -CHECKMATE + 14;
+        assert_eq!(round_trip(winning, 4, 9), Some(winning - 5));
+        assert_eq!(round_trip(losing, 4, 9), Some(losing + 5));
+        assert_eq!(round_trip(winning, 4, 4), Some(winning));
+        assert_eq!(round_trip(losing, 4, 4), Some(losing));
+    }
+
+    #[test]
+    fn search_data_keeps_its_fields() {
+        let best_move = ShortMove::new(MOVE_BITS);
+        for flag in [HashFlag::Exact, HashFlag::Alpha, HashFlag::Beta] {
+            for depth in [1, 17, MAX_STORED_DEPTH] {
+                for static_eval in [None, Some(-1), Some(0), Some(i16::MAX)] {
+                    let data = SearchData::create(depth, 0, flag, -123, static_eval, best_move);
+                    assert_eq!(data.depth(), depth);
+                    assert_eq!(data.flag() as u8, flag as u8);
+                    assert_eq!(data.static_eval(), static_eval);
+                    assert!(data.best_move() == best_move);
+                }
+            }
+        }
+        assert!(SearchData::new().static_eval().is_none());
+    }
+
+    #[test]
+    fn deep_search_data_is_stored_shallower() {
+        let data = SearchData::create(100, 0, HashFlag::Exact, 0, None, ShortMove::new(0));
+        assert_eq!(data.depth(), MAX_STORED_DEPTH);
+    }
+
+    // Stores a perft result under the key, so it can be recognized.
+    fn perft_tt(keys: &[ZobristKey]) -> TT<PerftData> {
+        let mut tt: TT<PerftData> = TT::new(1);
+        for (i, key) in keys.iter().enumerate() {
+            tt.insert(*key, PerftData::create(1, i as u64 + 100));
+        }
+        tt
+    }
+
+    fn found(tt: &TT<PerftData>, key: ZobristKey) -> Option<u64> {
+        tt.probe(key).and_then(|data| data.get(1))
+    }
+
+    #[test]
+    fn verification_distinguishes_keys_in_a_bucket() {
+        // Same upper half, so the same bucket; different lower halves.
+        let a = (7 << 32) | 0x1111;
+        let b = (7 << 32) | 0x2222;
+        let tt = perft_tt(&[a, b]);
+        assert_eq!(found(&tt, a), Some(100));
+        assert_eq!(found(&tt, b), Some(101));
+        assert_eq!(found(&tt, (7 << 32) | 0x3333), None);
+    }
+
+    #[test]
+    fn keys_sharing_bucket_and_low_bits_collide() {
+        // Keys that land in the same bucket and share their lower 32 bits
+        // can't be told apart. For a random key, the chance of such a
+        // false match is estimated_collision_probability().
+        let tt = perft_tt(&[(7 << 32) | 0x1111]);
+        let buckets = tt.total_buckets as u64;
+        let same_bucket = ((7 + buckets) << 32) | 0x1111;
+        let other_bucket = (8 << 32) | 0x1111;
+        assert_eq!(found(&tt, same_bucket), Some(100));
+        assert_eq!(found(&tt, other_bucket), None);
+
+        let p = tt.estimated_collision_probability();
+        assert!(p > 0.0 && p < 1e-9);
+    }
+
+    // Counts the used entries by looking at every entry in the TT.
+    fn scan_used_entries<D: IHashData + Copy>(tt: &TT<D>) -> usize {
+        tt.tt
+            .iter()
+            .flat_map(|b| b.bucket.iter())
+            .filter(|e| e.verification != 0)
+            .count()
+    }
+
+    #[test]
+    fn used_entries_match_a_scan_in_any_order() {
+        // All keys land in bucket 7. There are more keys than entries, so
+        // the bucket overflows; one key is stored twice with a different
+        // depth, and the last key has verification 0, like an empty entry.
+        let keys: [(ZobristKey, i8); 8] = [
+            ((7 << 32) | 0x1, 3),
+            ((7 << 32) | 0x2, 1),
+            ((7 << 32) | 0x3, 5),
+            ((7 << 32) | 0x4, 2),
+            ((7 << 32) | 0x5, 4),
+            ((7 << 32) | 0x2, 6),
+            ((7 << 32) | 0x6, 1),
+            (7 << 32, 2),
+        ];
+        let mut orders: Vec<Vec<usize>> = Vec::new();
+        for start in 0..keys.len() {
+            let rotation: Vec<usize> = (0..keys.len()).map(|i| (start + i) % keys.len()).collect();
+            orders.push(rotation.iter().rev().copied().collect());
+            orders.push(rotation);
+        }
+
+        for order in orders {
+            let mut tt: TT<PerftData> = TT::new(1);
+            for i in order {
+                let (key, depth) = keys[i];
+                tt.insert(key, PerftData::create(depth, i as u64));
+                assert_eq!(tt.used_entries, scan_used_entries(&tt));
+                assert!(tt.used_entries <= ENTRIES_PER_BUCKET);
+            }
+        }
+    }
+
+    // Fills the TT with perft data for a range of keys.
+    fn filled_tt(megabytes: usize) -> TT<PerftData> {
+        let mut tt: TT<PerftData> = TT::new(megabytes);
+        for i in 1..10_000u64 {
+            tt.insert(
+                i.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                PerftData::create(1, i),
+            );
+        }
+        tt
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn validate_accepts_inserts_and_resize() {
+        let mut tt = filled_tt(1);
+        tt.validate();
+        tt.resize(2);
+        tt.insert(KEY, PerftData::create(1, 1));
+        tt.validate();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "TT: position stored twice")]
+    fn validate_finds_a_corrupted_entry() {
+        let mut tt = filled_tt(1);
+        let index = tt.calculate_index(KEY);
+        let entry = Entry {
+            verification: tt.calculate_verification(KEY),
+            data: PerftData::create(1, 1),
+        };
+        tt.tt[index].bucket[0] = entry;
+        tt.tt[index].bucket[1] = entry;
+        tt.validate();
+    }
+
+    #[test]
+    fn collision_probability_grows_with_use() {
+        let p = 2f64.powi(-VERIFICATION_BITS);
+        let empty: TT<PerftData> = TT::new(1);
+        let nearly_empty = perft_tt(&[KEY]);
+        let mut full: TT<PerftData> = TT::new(1);
+        for i in 1..(full.total_buckets * ENTRIES_PER_BUCKET * 8) as u64 {
+            full.insert(
+                i.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                PerftData::create(1, i),
+            );
+        }
+        assert_eq!(full.hash_full(), 1000);
+
+        // About 1 / total_buckets used entries per bucket, against
+        // ENTRIES_PER_BUCKET in a full TT.
+        let low = nearly_empty.estimated_collision_probability();
+        let high = full.estimated_collision_probability();
+        let expected_high = ENTRIES_PER_BUCKET as f64 * p;
+
+        assert_eq!(empty.estimated_collision_probability(), 0.0);
+        assert!(low > 0.0 && low < p);
+        assert!(
+            (high - expected_high).abs() < expected_high * 1e-6,
+            "{high}"
+        );
+        assert!(high > low * 1000.0);
+    }
+
+    #[test]
+    fn empty_tt_is_safe_to_use() {
+        let mut tt: TT<SearchData> = TT::new(0);
+        let data = SearchData::create(5, 0, HashFlag::Exact, 100, None, ShortMove::new(0));
+
+        tt.insert(KEY, data);
+        assert!(tt.probe(KEY).is_none());
+        assert_eq!(tt.hash_full(), 0);
+        assert_eq!(tt.estimated_collision_probability(), 0.0);
+
+        tt.clear();
+        tt.resize(0);
+        tt.insert(KEY, data);
+        assert!(tt.probe(KEY).is_none());
+        tt.validate();
+    }
+
+    #[test]
+    fn search_entry_sizes() {
+        // The search data takes 8 bytes, the verification 4 more, so a
+        // bucket of four entries is 48 bytes: a megabyte holds 21845
+        // buckets, or 87380 entries.
+        assert_eq!(std::mem::size_of::<SearchData>(), 8);
+        assert_eq!(std::mem::size_of::<Entry<SearchData>>(), 12);
+        assert_eq!(std::mem::size_of::<Bucket<SearchData>>(), 48);
+
+        let tt: TT<SearchData> = TT::new(1);
+        assert_eq!(tt.total_buckets, 21_845);
+        assert_eq!(tt.total_buckets * ENTRIES_PER_BUCKET, 87_380);
     }
 }
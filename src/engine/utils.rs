@@ -23,17 +23,9 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{defs::ErrFatal, Engine};
 use crate::{
-    board::Board,
+    board::defs::MoveError,
     defs::{EngineRunResult, FEN_KIWIPETE_POSITION},
-    misc::parse,
-    misc::parse::PotentialMove,
-    movegen::{
-        defs::{Move, MoveList, MoveType},
-        MoveGenerator,
-    },
 };
-use if_chain::if_chain;
-use std::sync::Mutex;
 
 impl Engine {
     // This function sets up a position using a given FEN-string.
@@ -54,50 +46,12 @@ impl Engine {
     }
 
     // This function executes a move on the internal board, if it legal to
-    // do so in the given position.
-    pub fn execute_move(&mut self, m: String) -> bool {
-        // Prepare shorthand variables.
-        let empty = (0usize, 0usize, 0usize);
-        let potential_move = parse::algebraic_move_to_number(&m[..]).unwrap_or(empty);
-        let is_pseudo_legal = self.pseudo_legal(potential_move, &self.board, &self.mg);
-        let mut is_legal = false;
-
-        if let Ok(ips) = is_pseudo_legal {
-            is_legal = self.board.lock().expect(ErrFatal::LOCK).make(ips, &self.mg);
-        }
-        is_legal
-    }
-
-    // After the engine receives an incoming move, it checks if this move
-    // is actually in the list of pseudo-legal moves for this position.
-    pub fn pseudo_legal(
-        &self,
-        m: PotentialMove,
-        board: &Mutex<Board>,
-        mg: &MoveGenerator,
-    ) -> Result<Move, ()> {
-        let mut result = Err(());
-
-        // Get the pseudo-legal move list for this position.
-        let mut ml = MoveList::new();
-        let mtx_board = board.lock().expect(ErrFatal::LOCK);
-        mg.generate_moves(&mtx_board, &mut ml, MoveType::All);
-        std::mem::drop(mtx_board);
-
-        // Determine if the potential move is pseudo-legal. make() wil
-        // determine final legality when executing the move.
-        for i in 0..ml.len() {
-            let current = ml.get_move(i);
-            if_chain! {
-                if m.0 == current.from();
-                if m.1 == current.to();
-                if m.2 == current.promoted();
-                then {
-                    result = Ok(current);
-                    break;
-                }
-            }
-        }
-        result
+    // do so in the given position. If it isn't, the reason is returned.
+    pub fn execute_move(&mut self, m: String) -> Result<(), MoveError> {
+        self.board
+            .lock()
+            .expect(ErrFatal::LOCK)
+            .make_uci(&m, &self.mg)
+            .map(|_| ())
     }
 }
@@ -22,12 +22,19 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 pub mod defs;
+pub mod outposts;
+pub mod pawns;
 pub mod psqt;
 
-use crate::{board::Board, defs::Sides};
+use crate::{
+    board::{defs::Drawishness, Board},
+    defs::Sides,
+};
+use defs::MAX_PHASE;
+use pawns::PawnHash;
 use psqt::KING_EDGE;
 
-pub fn evaluate_position(board: &Board) -> i16 {
+pub fn evaluate_position(board: &Board, pawn_hash: &mut PawnHash) -> i16 {
     const KING_ONLY: i16 = 300; // PSQT-points
     let side = board.game_state.active_color as usize;
     let w_psqt = board.game_state.psqt[Sides::WHITE];
@@ -42,6 +49,16 @@ pub fn evaluate_position(board: &Board) -> i16 {
         value += w_king_edge - b_king_edge;
     }
 
+    // Add the pawn structure score, which is cached in the pawn hash.
+    value += pawns::evaluate(board, pawn_hash);
+
+    // Add the score for knights on outposts.
+    value += outposts::evaluate(board, board.phase());
+
+    // Pull the score toward a draw if the material makes a draw likely.
+    // A dead draw halves the score; a sharp position keeps all of it.
+    value = scale_toward_draw(value, board.drawishness());
+
     // This function calculates the evaluation from white's point of view:
     // a positive value means "white is better", a negative value means
     // "black is better". Alpha/Beta requires the value returned from the
@@ -53,3 +70,16 @@ pub fn evaluate_position(board: &Board) -> i16 {
 
     value
 }
+
+// Scales the value down by up to half, in proportion to the drawishness.
+fn scale_toward_draw(value: i16, drawishness: u8) -> i16 {
+    let full = 2 * Drawishness::MAX as i32;
+    (value as i32 * (full - drawishness as i32) / full) as i16
+}
+
+// Blends a middlegame and an endgame value according to the game phase.
+pub fn taper(mg: i16, eg: i16, phase: i16) -> i16 {
+    let mg_part = mg as i32 * phase as i32;
+    let eg_part = eg as i32 * (MAX_PHASE - phase) as i32;
+    ((mg_part + eg_part) / MAX_PHASE as i32) as i16
+}
@@ -20,3 +20,25 @@ for more details.
 You should have received a copy of the GNU General Public License along
 with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
+
+use crate::defs::NrOf;
+
+pub type PieceValues = [i16; NrOf::PIECE_TYPES + 1];
+
+// Piece values in centipawns, for the middlegame (MG) and the endgame
+// (EG). Board::material_balance() tapers between the two tables by the
+// game phase. Static exchange evaluation and MVV-LVA move ordering use
+// the middlegame values, so all three take their values from here and
+// can't drift apart. The evaluation itself doesn't use the tables: the
+// piece-square tables in psqt.rs include the value of the piece on each
+// square. The king has no material value because it can never be
+// exchanged. Order: King, Queen, Rook, Bishop, Knight, Pawn, None.
+pub const PIECE_VALUES_MG: PieceValues = [0, 900, 500, 330, 320, 100, 0];
+pub const PIECE_VALUES_EG: PieceValues = [0, 950, 550, 340, 330, 120, 0];
+
+// Game phase weights of each piece type, used to determine how far the
+// game has progressed from the middlegame toward the endgame. With all
+// pieces on the board, the phase is MAX_PHASE; with only kings and pawns
+// it is 0. Order: King, Queen, Rook, Bishop, Knight, Pawn, None.
+pub const PHASE_WEIGHTS: PieceValues = [0, 4, 2, 1, 1, 0, 0];
+pub const MAX_PHASE: i16 = 24;
@@ -0,0 +1,129 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// This file evaluates knight outposts. An outpost is a square in the
+// opponent's half of the board that is defended by one of our own pawns,
+// and that can never be attacked by an opponent pawn, because there are
+// no opponent pawns left in front of it on the files next to it. A knight
+// on such a square can't be chased away by pawns.
+
+use super::{
+    pawns::{adjacent_files, ranks_ahead},
+    taper,
+};
+use crate::{
+    board::{
+        defs::{Pieces, BB_FILES},
+        Board,
+    },
+    defs::{Bitboard, NrOf, Side, Sides, EMPTY},
+    misc::bits,
+};
+
+// Outpost bonus by the rank as seen from the knight's own side. Outposts
+// only exist on the 4th, 5th and 6th ranks.
+const OUTPOST_RANK: [i16; NrOf::RANKS] = [0, 0, 0, 10, 15, 20, 0, 0];
+
+// Additional outpost bonus by file: central outposts are worth more.
+const OUTPOST_FILE: [i16; NrOf::FILES] = [0, 0, 5, 10, 10, 5, 0, 0];
+
+// Outposts matter most in the middlegame. In the endgame, only this
+// percentage of the bonus remains.
+const OUTPOST_EG_PERCENT: i16 = 25;
+
+// Returns the knight outpost score from white's point of view.
+pub fn evaluate(board: &Board, phase: i16) -> i16 {
+    outposts_for_side(board, Sides::WHITE, phase) - outposts_for_side(board, Sides::BLACK, phase)
+}
+
+fn outposts_for_side(board: &Board, side: Side, phase: i16) -> i16 {
+    let opponent_pawns = board.get_pieces(Pieces::PAWN, side ^ 1);
+    let defended = pawn_attacks(board.get_pieces(Pieces::PAWN, side), side);
+    let knights = board.get_pieces(Pieces::KNIGHT, side) & defended;
+    let mut score: i16 = 0;
+
+    for square in bits::squares(knights) {
+        let (file, rank) = Board::square_on_file_rank(square);
+        let (file, rank) = (file as usize, rank as usize);
+
+        // The knight is on an outpost if no opponent pawn can ever attack
+        // its square.
+        let attack_span = adjacent_files(file) & ranks_ahead(side, rank);
+        if opponent_pawns & attack_span == EMPTY {
+            let relative_rank = if side == Sides::WHITE {
+                rank
+            } else {
+                NrOf::RANKS - 1 - rank
+            };
+            let mg = OUTPOST_RANK[relative_rank] + OUTPOST_FILE[file];
+            let eg = mg * OUTPOST_EG_PERCENT / 100;
+            score += taper(mg, eg, phase);
+        }
+    }
+
+    score
+}
+
+// Returns a bitboard of all the squares attacked by the given pawns.
+fn pawn_attacks(pawns: Bitboard, side: Side) -> Bitboard {
+    let not_a = !BB_FILES[0];
+    let not_h = !BB_FILES[NrOf::FILES - 1];
+
+    if side == Sides::WHITE {
+        ((pawns & not_a) << 7) | ((pawns & not_h) << 9)
+    } else {
+        ((pawns & not_a) >> 9) | ((pawns & not_h) >> 7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::defs::MAX_PHASE;
+
+    fn outposts(fen: &str) -> i16 {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        evaluate(&board, MAX_PHASE)
+    }
+
+    #[test]
+    fn knight_that_cant_be_challenged_scores_more() {
+        // The knight on d5 is defended by the e4 pawn. With the c-pawn on
+        // c7, black can chase it away with c7-c6.
+        let outpost = outposts("4k3/3p4/8/3N4/4P3/8/8/4K3 w - - 0 1");
+        let challenged = outposts("4k3/2pp4/8/3N4/4P3/8/8/4K3 w - - 0 1");
+        let undefended = outposts("4k3/3p4/8/3N4/8/4P3/8/4K3 w - - 0 1");
+
+        assert!(outpost > challenged);
+        assert_eq!(challenged, 0);
+        assert_eq!(undefended, 0);
+    }
+
+    #[test]
+    fn outposts_are_symmetric() {
+        let white = outposts("4k3/3p4/8/3N4/4P3/8/8/4K3 w - - 0 1");
+        let black = outposts("4k3/8/8/4p3/3n4/8/3P4/4K3 w - - 0 1");
+        assert_eq!(white, -black);
+    }
+}
@@ -0,0 +1,238 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// This file evaluates the pawn structure: doubled, isolated and passed
+// pawns. Because the pawn structure changes much less often than the
+// rest of the position, the result is cached in a pawn hash table that is
+// keyed by the pawn-only Zobrist key in the board's game state.
+
+use crate::{
+    board::{
+        defs::{Pieces, ZobristKey, BB_FILES, BB_SQUARES},
+        Board,
+    },
+    defs::{Bitboard, NrOf, Side, Sides, EMPTY},
+    misc::bits,
+};
+
+const DOUBLED_PAWN: i16 = -10;
+const ISOLATED_PAWN: i16 = -10;
+
+// Passed pawn bonus, indexed by the rank as seen from the pawn's own side.
+const PASSED_PAWN: [i16; NrOf::RANKS] = [0, 5, 10, 20, 35, 60, 100, 0];
+
+// Number of entries in the pawn hash table. This must be a power of two,
+// so the index can be calculated by masking the key.
+const PAWN_HASH_ENTRIES: usize = 16_384;
+
+#[derive(Copy, Clone)]
+struct PawnHashEntry {
+    key: ZobristKey,
+    score: i16,
+}
+
+impl PawnHashEntry {
+    fn new() -> Self {
+        Self { key: 0, score: 0 }
+    }
+}
+
+// The pawn hash table has a fixed size, and each key maps to exactly one
+// entry. A new score always replaces the one already in the entry.
+pub struct PawnHash {
+    entries: Vec<PawnHashEntry>,
+}
+
+impl PawnHash {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![PawnHashEntry::new(); PAWN_HASH_ENTRIES],
+        }
+    }
+
+    pub fn probe(&self, key: ZobristKey) -> Option<i16> {
+        let entry = &self.entries[PawnHash::index(key)];
+        if entry.key == key {
+            Some(entry.score)
+        } else {
+            None
+        }
+    }
+
+    pub fn store(&mut self, key: ZobristKey, score: i16) {
+        self.entries[PawnHash::index(key)] = PawnHashEntry { key, score };
+    }
+
+    fn index(key: ZobristKey) -> usize {
+        (key as usize) & (PAWN_HASH_ENTRIES - 1)
+    }
+}
+
+// Returns the pawn structure score from white's point of view. The score
+// is taken from the pawn hash if it is there; otherwise it is calculated
+// and stored. (A position without pawns has key 0, which is also the key
+// of an empty entry; this works out, because its score is 0 as well.)
+pub fn evaluate(board: &Board, pawn_hash: &mut PawnHash) -> i16 {
+    let key = board.game_state.pawn_key;
+
+    if let Some(score) = pawn_hash.probe(key) {
+        return score;
+    }
+
+    let score = pawn_structure(board);
+    pawn_hash.store(key, score);
+
+    score
+}
+
+// Calculates the pawn structure score from scratch, from white's point of
+// view.
+pub fn pawn_structure(board: &Board) -> i16 {
+    pawn_structure_for_side(board, Sides::WHITE) - pawn_structure_for_side(board, Sides::BLACK)
+}
+
+fn pawn_structure_for_side(board: &Board, side: Side) -> i16 {
+    let own_pawns = board.get_pieces(Pieces::PAWN, side);
+    let passed = passed_pawns(board, side);
+    let mut score: i16 = 0;
+
+    // Each pawn beyond the first one on a file is a doubled pawn.
+    for bb_file in BB_FILES.iter() {
+        let on_file = (own_pawns & bb_file).count_ones() as i16;
+        if on_file > 1 {
+            score += (on_file - 1) * DOUBLED_PAWN;
+        }
+    }
+
+    for square in bits::squares(own_pawns) {
+        let (file, rank) = Board::square_on_file_rank(square);
+        let neighbours = adjacent_files(file as usize);
+
+        // A pawn without friendly pawns on the files next to it is isolated.
+        if own_pawns & neighbours == EMPTY {
+            score += ISOLATED_PAWN;
+        }
+
+        // A passed pawn gets a bonus that grows as it advances.
+        if passed & BB_SQUARES[square] > 0 {
+            let relative_rank = if side == Sides::WHITE {
+                rank as usize
+            } else {
+                NrOf::RANKS - 1 - rank as usize
+            };
+            score += PASSED_PAWN[relative_rank];
+        }
+    }
+
+    score
+}
+
+// Returns the passed pawns of the given side: pawns that don't have
+// opponent pawns in front of them, on their own file or the files next to
+// them.
+pub fn passed_pawns(board: &Board, side: Side) -> Bitboard {
+    let opponent_pawns = board.get_pieces(Pieces::PAWN, side ^ 1);
+    let mut passed = EMPTY;
+
+    for square in bits::squares(board.get_pieces(Pieces::PAWN, side)) {
+        let (file, rank) = Board::square_on_file_rank(square);
+        let file = file as usize;
+        let front_span = (adjacent_files(file) | BB_FILES[file]) & ranks_ahead(side, rank as usize);
+        if opponent_pawns & front_span == EMPTY {
+            passed |= BB_SQUARES[square];
+        }
+    }
+
+    passed
+}
+
+// Returns a bitboard of the files to the left and right of the given file.
+pub fn adjacent_files(file: usize) -> Bitboard {
+    let left = if file > 0 { BB_FILES[file - 1] } else { EMPTY };
+    let right = if file < NrOf::FILES - 1 {
+        BB_FILES[file + 1]
+    } else {
+        EMPTY
+    };
+    left | right
+}
+
+// Returns a bitboard of all the ranks in front of the given rank, as seen
+// from the given side.
+pub fn ranks_ahead(side: Side, rank: usize) -> Bitboard {
+    if side == Sides::WHITE {
+        u64::MAX
+            .checked_shl(((rank + 1) * 8) as u32)
+            .unwrap_or(EMPTY)
+    } else {
+        (1u64 << (rank * 8)) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        board
+    }
+
+    #[test]
+    fn cached_score_equals_fresh_score() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            // Doubled, isolated and passed pawns.
+            "4k3/p1p5/8/1P6/8/1P6/7P/4K3 w - - 0 1",
+            "4k3/3p4/3p4/8/8/8/6PP/4K3 b - - 0 1",
+            "8/8/8/8/8/8/8/K6k w - - 0 1",
+        ];
+        let mut pawn_hash = PawnHash::new();
+
+        for fen in fens {
+            let board = board(fen);
+            let fresh = pawn_structure(&board);
+            assert_eq!(evaluate(&board, &mut pawn_hash), fresh, "{fen}");
+            assert_eq!(pawn_hash.probe(board.game_state.pawn_key), Some(fresh));
+            assert_eq!(evaluate(&board, &mut pawn_hash), fresh, "{fen}");
+        }
+    }
+
+    #[test]
+    fn same_pawns_hit_the_cache() {
+        let with_queens = board("3qk3/pp3ppp/8/2p5/8/3P4/PPP2PPP/3QK3 w - - 0 1");
+        let with_rooks = board("r3k3/pp3ppp/8/2p5/8/3P4/PPP2PPP/R3K3 b - - 0 1");
+        let other_pawns = board("r3k3/pp3ppp/8/2p5/3P4/8/PPP2PPP/R3K3 b - - 0 1");
+        let mut pawn_hash = PawnHash::new();
+
+        let key = with_queens.game_state.pawn_key;
+        assert_eq!(with_rooks.game_state.pawn_key, key);
+        assert!(pawn_hash.probe(key).is_none());
+
+        let score = evaluate(&with_queens, &mut pawn_hash);
+        assert_eq!(pawn_hash.probe(key), Some(score));
+        assert_eq!(evaluate(&with_rooks, &mut pawn_hash), score);
+        assert!(pawn_hash.probe(other_pawns.game_state.pawn_key).is_none());
+    }
+}
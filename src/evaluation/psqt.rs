@@ -26,7 +26,7 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 // diagram, with A1 on the lower left corner.
 
 use crate::{
-    board::Board,
+    board::{defs::Sq, Board},
     defs::{NrOf, Sides},
     misc::bits,
 };
@@ -170,21 +170,8 @@ pub const KING_EDGE: Psqt = [
 // converstion. (Super-impose the square numbers on top of the PSQT with
 // BLACK square names.)
 
-// This results in the following converstion table, from aquare number
-// to PSQT element, needed for WHITE only::
-
-#[allow(dead_code)]
-#[rustfmt::skip]
-pub const FLIP: [usize; 64] = [
-    56, 57, 58, 59, 60, 61, 62, 63,
-    48, 49, 50, 51, 52, 53, 54, 55,
-    40, 41, 42, 43, 44, 45, 46, 47,
-    32, 33, 34, 35, 36, 37, 38, 39,
-    24, 25, 26, 27, 28, 29, 30, 31,
-    16, 17, 18, 19, 20, 21, 22, 23,
-     8,  9, 10, 11, 12, 13, 14, 15,
-     0,  1,  2,  3,  4,  5,  6,  7,
-];
+// For WHITE, the square number has to be mirrored vertically to find the
+// PSQT element. This is what Sq::flip() does.
 
 // Apply PSQT's to position
 pub fn apply(board: &Board) -> (i16, i16) {
@@ -201,7 +188,7 @@ pub fn apply(board: &Board) -> (i16, i16) {
         // Iterate over pieces of the current piece_type for white.
         while white_pieces > 0 {
             let square = bits::next(&mut white_pieces);
-            w_psqt += PSQT_MG[piece_type][FLIP[square]] as i16;
+            w_psqt += PSQT_MG[piece_type][Sq::new(square).flip().index()] as i16;
         }
 
         // Iterate over pieces of the current piece_type for black.
@@ -22,5 +22,8 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 pub mod epds;
+pub mod legalcheck;
+pub mod tactics;
 pub mod testsuite;
+pub mod ttbench;
 pub mod wizardry;
@@ -23,7 +23,7 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 // ===== Large EPD test suite =====
 
-pub const LARGE_TEST_EPDS: [&str; 172] = [
+pub const LARGE_TEST_EPDS: [&str; 173] = [
     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902 ;D4 197281 ;D5 4865609 ;D6 119060324",
     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902 ;D4 197281 ;D5 4865609 ;D6 119060324",
     "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 ;D1 48 ;D2 2039 ;D3 97862 ;D4 4085603 ;D5 193690690 ;D6 8031647685",
@@ -229,4 +229,20 @@ pub const LARGE_TEST_EPDS: [&str; 172] = [
     // long castling impossible although the rook never moved away from its corner
 	"1k6/8/8/8/R7/1n6/8/R3K3 b Q - 0 1 ;D5 346695",
     "r3k3/8/1N6/r7/8/8/8/1K6 w q - 0 1 ;D5 346695",
+
+    // maximum number of legal moves (218); must fit in the move list
+    "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1 ;D1 218 ;D2 99 ;D3 19073 ;D4 85043 ;D5 13853661 ;D6 115892741",
+];
+
+// ===== Small tactics test suite =====
+
+// Positions in EPD format with "bm" (best move) and/or "am" (avoid move)
+// operations, to check if the engine finds the right move.
+pub const TACTICS_EPDS: [&str; 6] = [
+    "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";",
+    "r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - bm Qxh7+; id \"WAC.004\";",
+    "5k2/6pp/p1qN4/1p1p4/3P4/2PKP2Q/PP3r2/3R4 b - - bm Qc4+; id \"WAC.005\";",
+    "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - bm Ra8#; id \"Back rank mate\";",
+    "8/2q1P1k1/8/8/8/8/8/K7 w - - bm e8=N+; id \"Underpromotion fork\";",
+    "k7/8/2K5/1Q6/8/8/8/8 w - - am Qb6; id \"Avoid stalemate\";",
 ];
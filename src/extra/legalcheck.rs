@@ -0,0 +1,119 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// legalcheck.rs checks legal move generation against known perft results.
+// For each position in LARGE_TEST_EPDS, it counts the leaf nodes up to a
+// small depth, using only Board::legal_moves(), and compares them with
+// the expected counts in the EPD. Those counts were not produced by this
+// engine, so a bug in move generation, in make() or in unmake() can't
+// hide behind a second count that is wrong in the same way.
+
+use crate::{board::Board, extra::epds::LARGE_TEST_EPDS, movegen::MoveGenerator};
+
+const DEPTH: i8 = 4;
+const SEMI_COLON: char = ';';
+const SPACE: char = ' ';
+
+pub fn run() {
+    match check(DEPTH) {
+        Ok(tests) => println!("Legal move counts up to depth {DEPTH} - {tests} positions - OK"),
+        Err(error) => {
+            println!("Legal move counts - Fail\n{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+// Counts the leaf nodes of each test position up to the given depth.
+// Returns the number of positions checked, or a description of the first
+// count that differs from the expected one.
+pub fn check(max_depth: i8) -> Result<usize, String> {
+    let mg = MoveGenerator::new();
+    let mut board = Board::new();
+
+    for (i, epd) in LARGE_TEST_EPDS.iter().enumerate() {
+        let mut test_data = epd.split(SEMI_COLON).map(|s| s.trim());
+        let fen = test_data.next().unwrap_or_default();
+
+        if board.fen_read(Some(fen)).is_err() {
+            return Err(format!("Test {}: error in FEN: {fen}", i + 1));
+        }
+
+        for depth_ln in test_data {
+            let Some((depth, expected_ln)) = parse_depth_ln(depth_ln) else {
+                return Err(format!("Test {}: error in test data: {depth_ln}", i + 1));
+            };
+
+            if depth > max_depth {
+                break;
+            }
+
+            let found_ln = leaf_nodes(&mut board, depth, &mg);
+            if found_ln != expected_ln {
+                return Err(format!(
+                    "Test {}: {fen}\nDepth {depth}: expected {expected_ln}, found {found_ln}",
+                    i + 1
+                ));
+            }
+        }
+    }
+
+    Ok(LARGE_TEST_EPDS.len())
+}
+
+// Splits "D1 20" into the depth (1) and the expected leaf nodes (20).
+fn parse_depth_ln(depth_ln: &str) -> Option<(i8, u64)> {
+    let (depth, leaf_nodes) = depth_ln.split_once(SPACE)?;
+    let depth = depth.strip_prefix('D')?.parse().ok()?;
+    let leaf_nodes = leaf_nodes.parse().ok()?;
+    Some((depth, leaf_nodes))
+}
+
+// Counts the leaf nodes at the given depth, with the legal moves only.
+fn leaf_nodes(board: &mut Board, depth: i8, mg: &MoveGenerator) -> u64 {
+    let legal_moves = board.legal_moves(mg);
+    if depth <= 1 {
+        return legal_moves.len() as u64;
+    }
+
+    let mut leaf_nodes = 0;
+    for i in 0..legal_moves.len() {
+        if board.make(legal_moves.get_move(i), mg) {
+            leaf_nodes += self::leaf_nodes(board, depth - 1, mg);
+            board.unmake();
+        }
+    }
+
+    leaf_nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One ply less than the command-line check, to keep the test quick.
+    #[test]
+    fn legal_move_counts_match_perft_results() {
+        assert_eq!(check(DEPTH - 1), Ok(LARGE_TEST_EPDS.len()));
+    }
+}
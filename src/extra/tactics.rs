@@ -0,0 +1,174 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// tactics.rs runs a tactics test suite. Each position is given in EPD
+// format: the first four fields of a FEN-string, followed by operations
+// such as "bm" (best move), "am" (avoid move) and "id". The engine
+// searches each position for a fixed amount of time, and the test passes
+// if the engine plays one of the best moves, and none of the moves it
+// should avoid.
+
+use crate::{
+    board::Board,
+    engine::defs::{ErrFatal, Information, SearchData, TT},
+    extra::epds::TACTICS_EPDS,
+    movegen::{defs::Move, MoveGenerator},
+    search::{
+        defs::{SearchControl, SearchMode, SearchParams, SearchReport},
+        Search,
+    },
+};
+use std::sync::{Arc, Mutex};
+
+const SEMI_COLON: char = ';';
+const SPACE: char = ' ';
+const QUOTE: char = '"';
+const EPD_POSITION_FIELDS: usize = 4;
+const MOVE_TIME: u128 = 1_000; // Milliseconds per position
+
+// A position in EPD format, split into its parts.
+pub struct Epd {
+    pub fen: String,
+    pub id: String,
+    pub best_moves: Vec<String>,
+    pub avoid_moves: Vec<String>,
+}
+
+impl Epd {
+    // Parses an EPD line. Returns None if the position part is incomplete.
+    // Unknown operations are ignored.
+    pub fn parse(line: &str) -> Option<Epd> {
+        let fields: Vec<&str> = line.trim().splitn(EPD_POSITION_FIELDS + 1, SPACE).collect();
+        if fields.len() < EPD_POSITION_FIELDS {
+            return None;
+        }
+
+        let mut epd = Epd {
+            fen: fields[..EPD_POSITION_FIELDS].join(" "),
+            id: String::from(""),
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+        };
+
+        // Each operation is an opcode followed by its operands, and
+        // operations are separated by semicolons.
+        let operations = fields.get(EPD_POSITION_FIELDS).unwrap_or(&"");
+        for operation in operations.split(SEMI_COLON) {
+            let mut parts = operation.split_whitespace();
+            let operands: Vec<String> = parts.clone().skip(1).map(|s| s.to_string()).collect();
+
+            match parts.next() {
+                Some("bm") => epd.best_moves = operands,
+                Some("am") => epd.avoid_moves = operands,
+                Some("id") => epd.id = operands.join(" ").trim_matches(QUOTE).to_string(),
+                _ => (),
+            }
+        }
+
+        Some(epd)
+    }
+}
+
+pub fn run(tt_size: usize) {
+    let mg = Arc::new(MoveGenerator::new());
+    let board = Arc::new(Mutex::new(Board::new()));
+    let tt = Arc::new(Mutex::new(TT::<SearchData>::new(tt_size)));
+    let (report_tx, report_rx) = crossbeam_channel::unbounded::<Information>();
+    let mut search = Search::new();
+    let mut solved = 0;
+
+    search.init(
+        report_tx,
+        Arc::clone(&board),
+        Arc::clone(&mg),
+        Arc::clone(&tt),
+        tt_size > 0,
+    );
+
+    for (i, line) in TACTICS_EPDS.iter().enumerate() {
+        print!("Test {} from {}: ", i + 1, TACTICS_EPDS.len());
+
+        let Some(epd) = Epd::parse(line) else {
+            println!("Error parsing EPD: {line}");
+            continue;
+        };
+
+        // Set up the position and convert the SAN moves.
+        let mut b = board.lock().expect(ErrFatal::LOCK);
+        if b.fen_read(Some(&epd.fen)).is_err() {
+            println!("Error in FEN: {}", epd.fen);
+            continue;
+        }
+        let best = to_moves(&mut b, &epd.best_moves, &mg);
+        let avoid = to_moves(&mut b, &epd.avoid_moves, &mg);
+        std::mem::drop(b);
+
+        if best.is_none() || avoid.is_none() {
+            println!("Error in moves for {}", epd.id);
+            continue;
+        }
+
+        // Search the position and wait for the result.
+        tt.lock().expect(ErrFatal::LOCK).clear();
+        let mut sp = SearchParams::new();
+        sp.search_mode = SearchMode::MoveTime;
+        sp.move_time = MOVE_TIME;
+        sp.quiet = true;
+        search.send(SearchControl::Start(sp));
+        let found = wait_for_best_move(&report_rx);
+
+        let is_best = best.is_some_and(|b| b.is_empty() || b.contains(&found.get_move()));
+        let is_avoided = avoid.is_some_and(|a| !a.contains(&found.get_move()));
+        let is_ok = is_best && is_avoided;
+        if is_ok {
+            solved += 1;
+        }
+
+        let result = if is_ok { "OK" } else { "Fail" };
+        let played = board.lock().expect(ErrFatal::LOCK).move_to_san(found, &mg);
+        println!("{} - played {played} - {result}", epd.id);
+    }
+
+    println!("Solved {solved} from {} positions.", TACTICS_EPDS.len());
+
+    search.send(SearchControl::Quit);
+    search.wait_for_shutdown();
+}
+
+// Converts SAN moves into the engine's moves. Returns None if one of them
+// is not a legal move.
+fn to_moves(board: &mut Board, san_moves: &[String], mg: &MoveGenerator) -> Option<Vec<u32>> {
+    san_moves
+        .iter()
+        .map(|san| board.san_to_move(san, mg).map(|m| m.get_move()))
+        .collect()
+}
+
+fn wait_for_best_move(report_rx: &crossbeam_channel::Receiver<Information>) -> Move {
+    loop {
+        let information = report_rx.recv().expect(ErrFatal::CHANNEL);
+        if let Information::Search(SearchReport::Finished(result)) = information {
+            return result.best_move;
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+use crate::{
+    board::{defs::ZobristKey, Board},
+    engine::defs::{HashFlag, SearchData, TT},
+    movegen::{defs::ShortMove, MoveGenerator},
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use std::time::Instant;
+
+const NR_OF_KEYS: usize = 2_000_000; // Number of inserts, and of probes.
+const MAX_GAME_PLY: usize = 200; // Start a new random game after this.
+const MIN_OPS_PER_SECOND: u128 = 1_000_000; // Below this, the TT is too slow.
+const RNG_SEED: [u8; 32] = [42; 32];
+
+// The TT benchmark can be used by compiling the "extra" module into the
+// engine, and then adding the "-b" option on the command line. It plays
+// random games to collect Zobrist keys of positions that can actually
+// occur, and then measures how fast these keys can be inserted into and
+// probed from the search TT. The benchmark uses a fixed seed, so the
+// key distribution is the same on every run. Returns false if the TT is
+// slower than MIN_OPS_PER_SECOND.
+pub fn run(megabytes: usize) -> bool {
+    if megabytes == 0 {
+        println!("TT benchmark: the TT is disabled (hash size is 0 MB).");
+        return true;
+    }
+
+    println!("Collecting {NR_OF_KEYS} keys from random games...");
+    let keys = collect_keys();
+    let mut tt: TT<SearchData> = TT::new(megabytes);
+
+    // Insert all the keys. Vary the depth, so the bucket's replacement
+    // scheme does some actual work.
+    let now = Instant::now();
+    for (i, key) in keys.iter().enumerate() {
+        let depth = (i % 16) as i8;
+        let data = SearchData::create(depth, 0, HashFlag::Exact, 0, None, ShortMove::new(0));
+        tt.insert(*key, data);
+    }
+    let insert_time = now.elapsed().as_millis();
+
+    // Probe for all the keys, and count how many of them are still found.
+    let now = Instant::now();
+    let mut hits: usize = 0;
+    for key in keys.iter() {
+        if tt.probe(*key).is_some() {
+            hits += 1;
+        }
+    }
+    let probe_time = now.elapsed().as_millis();
+
+    let insert_ops = ops_per_second(keys.len(), insert_time);
+    let probe_ops = ops_per_second(keys.len(), probe_time);
+    let hit_rate = (hits as f64 / keys.len() as f64) * 100f64;
+    let is_ok = insert_ops >= MIN_OPS_PER_SECOND && probe_ops >= MIN_OPS_PER_SECOND;
+
+    println!("TT size: {megabytes} MB, hash full: {}", tt.hash_full());
+    println!("Insert: {insert_time} ms ({insert_ops} ops/sec)");
+    println!("Probe: {probe_time} ms ({probe_ops} ops/sec)");
+    println!("Hit rate: {hit_rate:.2}%");
+    println!(
+        "Estimated false positive rate per probe: {:.3e}",
+        tt.estimated_collision_probability()
+    );
+    println!(
+        "Result: {} (minimum: {MIN_OPS_PER_SECOND} ops/sec)",
+        if is_ok { "OK" } else { "Fail" }
+    );
+
+    is_ok
+}
+
+// Play random legal moves and collect the Zobrist key of each position.
+// When a game ends, or becomes too long, start a new one.
+fn collect_keys() -> Vec<ZobristKey> {
+    let mg = MoveGenerator::new();
+    let mut random = ChaChaRng::from_seed(RNG_SEED);
+    let mut board = Board::new();
+    let mut keys: Vec<ZobristKey> = Vec::with_capacity(NR_OF_KEYS);
+
+    while keys.len() < NR_OF_KEYS {
+        if board.history.len() == 0 || board.history.len() >= MAX_GAME_PLY {
+            board.fen_read(None).expect("Start position must be valid.");
+        }
+
+        let legal_moves = board.legal_moves(&mg);
+        if legal_moves.len() == 0 {
+            board.fen_read(None).expect("Start position must be valid.");
+            continue;
+        }
+
+        let index = random.gen_range(0..legal_moves.len());
+        board.make(legal_moves.get_move(index), &mg);
+        keys.push(board.game_state.zobrist_key);
+    }
+
+    keys
+}
+
+// If the operations took less than a millisecond, count it as one.
+fn ops_per_second(ops: usize, msecs: u128) -> u128 {
+    (ops as u128 * 1000) / msecs.max(1)
+}
@@ -30,3 +30,43 @@ pub fn next(bitboard: &mut Bitboard) -> Square {
     *bitboard ^= 1u64 << square;
     square
 }
+
+// Iterates over the set bits of a bitboard, from A1 up to H8. Each step
+// provides the square of the next piece, as next() does, but the given
+// bitboard itself is left unchanged.
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = Square> {
+    std::iter::from_fn(move || {
+        if bitboard > 0 {
+            Some(next(&mut bitboard))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_in_ascending_order() {
+        let bitboard: Bitboard = (1 << 0) | (1 << 9) | (1 << 27) | (1 << 63);
+        let found: Vec<Square> = squares(bitboard).collect();
+        assert_eq!(found, vec![0, 9, 27, 63]);
+    }
+
+    #[test]
+    fn squares_of_empty_and_full_bitboards() {
+        assert_eq!(squares(0).count(), 0);
+        assert!(squares(u64::MAX).eq(0..64));
+    }
+
+    #[test]
+    fn next_unsets_the_lowest_bit() {
+        let mut bitboard: Bitboard = (1 << 12) | (1 << 40);
+        assert_eq!(next(&mut bitboard), 12);
+        assert_eq!(bitboard, 1 << 40);
+        assert_eq!(next(&mut bitboard), 40);
+        assert_eq!(bitboard, 0);
+    }
+}
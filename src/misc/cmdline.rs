@@ -79,6 +79,21 @@ impl CmdLineArgs {
     const EPD_TEST_LONG: &'static str = "epdtest";
     const EPD_TEST_SHORT: char = 'e';
     const EPD_TEST_HELP: &'static str = "Run EPD Test Suite";
+
+    // TT benchmark
+    const TT_BENCH_LONG: &'static str = "ttbench";
+    const TT_BENCH_SHORT: char = 'b';
+    const TT_BENCH_HELP: &'static str = "Run TT insert/probe benchmark";
+
+    // Tactics test suite
+    const TACTICS_LONG: &'static str = "tactics";
+    const TACTICS_SHORT: char = 'a';
+    const TACTICS_HELP: &'static str = "Run EPD tactics test suite";
+
+    // Legal move generation cross-check
+    const LEGAL_CHECK_LONG: &'static str = "legalcheck";
+    const LEGAL_CHECK_SHORT: char = 'l';
+    const LEGAL_CHECK_HELP: &'static str = "Check legal move generation against perft results";
 }
 
 pub struct CmdLine {
@@ -145,6 +160,21 @@ impl CmdLine {
         self.arguments.get_flag(CmdLineArgs::EPD_TEST_LONG)
     }
 
+    #[cfg(feature = "extra")]
+    pub fn has_tt_bench(&self) -> bool {
+        self.arguments.get_flag(CmdLineArgs::TT_BENCH_LONG)
+    }
+
+    #[cfg(feature = "extra")]
+    pub fn has_tactics(&self) -> bool {
+        self.arguments.get_flag(CmdLineArgs::TACTICS_LONG)
+    }
+
+    #[cfg(feature = "extra")]
+    pub fn has_legal_check(&self) -> bool {
+        self.arguments.get_flag(CmdLineArgs::LEGAL_CHECK_LONG)
+    }
+
     fn get() -> ArgMatches {
         let mut cmd_line = clap::Command::new(About::ENGINE)
             .version(About::VERSION)
@@ -222,6 +252,27 @@ impl CmdLine {
                         .long(CmdLineArgs::EPD_TEST_LONG)
                         .help(CmdLineArgs::EPD_TEST_HELP)
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new(CmdLineArgs::TT_BENCH_LONG)
+                        .short(CmdLineArgs::TT_BENCH_SHORT)
+                        .long(CmdLineArgs::TT_BENCH_LONG)
+                        .help(CmdLineArgs::TT_BENCH_HELP)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new(CmdLineArgs::TACTICS_LONG)
+                        .short(CmdLineArgs::TACTICS_SHORT)
+                        .long(CmdLineArgs::TACTICS_LONG)
+                        .help(CmdLineArgs::TACTICS_HELP)
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new(CmdLineArgs::LEGAL_CHECK_LONG)
+                        .short(CmdLineArgs::LEGAL_CHECK_SHORT)
+                        .long(CmdLineArgs::LEGAL_CHECK_LONG)
+                        .help(CmdLineArgs::LEGAL_CHECK_HELP)
+                        .action(ArgAction::SetTrue),
                 );
         }
 
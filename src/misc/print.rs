@@ -140,7 +140,7 @@ fn metadata(board: &Board) {
     let active_color = if is_white { "White" } else { "Black" };
     let castling = castling_as_string(board.game_state.castling);
     let en_passant = match board.game_state.en_passant {
-        Some(ep) => SQUARE_NAME[ep as usize],
+        Some(ep) => SQUARE_NAME[ep.index()],
         None => "-",
     };
     let hmc = board.game_state.halfmove_clock;
@@ -29,10 +29,10 @@ mod movelist;
 
 use crate::{
     board::{
-        defs::{Pieces, Squares, BB_RANKS, BB_SQUARES},
+        defs::{Pieces, BB_RANKS, BB_SQUARES},
         Board,
     },
-    defs::{Bitboard, Castling, NrOf, Piece, Side, Sides, Square, EMPTY},
+    defs::{Bitboard, NrOf, Piece, Side, Sides, Square, EMPTY},
     misc::bits,
 };
 use defs::{Move, MoveType, Shift};
@@ -205,7 +205,7 @@ impl MoveGenerator {
                 let bb_targets = self.get_pawn_attacks(us, from);
                 let bb_captures = bb_targets & bb_opponent_pieces;
                 let bb_ep_capture = match board.game_state.en_passant {
-                    Some(ep) => bb_targets & BB_SQUARES[ep as usize],
+                    Some(ep) => bb_targets & BB_SQUARES[ep.index()],
                     None => 0,
                 };
                 bb_moves |= bb_captures | bb_ep_capture;
@@ -216,77 +216,19 @@ impl MoveGenerator {
     }
 
     pub fn castling(&self, board: &Board, list: &mut MoveList) {
-        // Create shorthand variables.
         let us = board.us();
-        let opponent = board.opponent();
-        let castle_perms_white = (board.game_state.castling & (Castling::WK | Castling::WQ)) > 0;
-        let castle_perms_black = (board.game_state.castling & (Castling::BK | Castling::BQ)) > 0;
-        let bb_occupancy = board.occupancy();
-        let mut bb_king = board.get_pieces(Pieces::KING, us);
-        let from = bits::next(&mut bb_king);
-
-        // Generate castling moves for white.
-        if us == Sides::WHITE && castle_perms_white {
-            // Kingside
-            if board.game_state.castling & Castling::WK > 0 {
-                let bb_kingside_blockers = BB_SQUARES[Squares::F1] | BB_SQUARES[Squares::G1];
-                let is_kingside_blocked = (bb_occupancy & bb_kingside_blockers) > 0;
-
-                if !is_kingside_blocked
-                    && !self.square_attacked(board, opponent, Squares::E1)
-                    && !self.square_attacked(board, opponent, Squares::F1)
-                {
-                    let to = BB_SQUARES[from] << 2;
-                    self.add_move(board, Pieces::KING, from, to, list);
-                }
-            }
+        let from = board.king_square(us);
 
-            if board.game_state.castling & Castling::WQ > 0 {
-                // Queenside
-                let bb_queenside_blockers =
-                    BB_SQUARES[Squares::B1] | BB_SQUARES[Squares::C1] | BB_SQUARES[Squares::D1];
-                let is_queenside_blocked = (bb_occupancy & bb_queenside_blockers) > 0;
-
-                if !is_queenside_blocked
-                    && !self.square_attacked(board, opponent, Squares::E1)
-                    && !self.square_attacked(board, opponent, Squares::D1)
-                {
-                    let to = BB_SQUARES[from] >> 2;
-                    self.add_move(board, Pieces::KING, from, to, list);
-                }
-            }
+        // Kingside
+        if board.can_castle(self, us, false) {
+            let to = BB_SQUARES[from] << 2;
+            self.add_move(board, Pieces::KING, from, to, list);
         }
 
-        // Generate castling moves for black.
-        if us == Sides::BLACK && castle_perms_black {
-            // Kingside
-            if board.game_state.castling & Castling::BK > 0 {
-                let bb_kingside_blockers = BB_SQUARES[Squares::F8] | BB_SQUARES[Squares::G8];
-                let is_kingside_blocked = (bb_occupancy & bb_kingside_blockers) > 0;
-
-                if !is_kingside_blocked
-                    && !self.square_attacked(board, opponent, Squares::E8)
-                    && !self.square_attacked(board, opponent, Squares::F8)
-                {
-                    let to = BB_SQUARES[from] << 2;
-                    self.add_move(board, Pieces::KING, from, to, list);
-                }
-            }
-
-            // Queenside
-            if board.game_state.castling & Castling::BQ > 0 {
-                let bb_queenside_blockers =
-                    BB_SQUARES[Squares::B8] | BB_SQUARES[Squares::C8] | BB_SQUARES[Squares::D8];
-                let is_queenside_blocked = (bb_occupancy & bb_queenside_blockers) > 0;
-
-                if !is_queenside_blocked
-                    && !self.square_attacked(board, opponent, Squares::E8)
-                    && !self.square_attacked(board, opponent, Squares::D8)
-                {
-                    let to = BB_SQUARES[from] >> 2;
-                    self.add_move(board, Pieces::KING, from, to, list);
-                }
-            }
+        // Queenside
+        if board.can_castle(self, us, true) {
+            let to = BB_SQUARES[from] >> 2;
+            self.add_move(board, Pieces::KING, from, to, list);
         }
     }
 
@@ -311,7 +253,7 @@ impl MoveGenerator {
             let to_square = bits::next(&mut bb_to);
             let capture = board.piece_list[to_square];
             let en_passant = match board.game_state.en_passant {
-                Some(square) => is_pawn && (square as usize == to_square),
+                Some(square) => is_pawn && (square.index() == to_square),
                 None => false,
             };
             let promotion = is_pawn && Board::square_on_rank(to_square, promotion_rank);
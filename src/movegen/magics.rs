@@ -88,7 +88,7 @@ pub struct Magic {
     pub nr: u64,
 }
 
-/**
+/*
  * get_index() is the actual function that gets the magic index into the attack table.
  * The attack table is a perfect hash. This means the following.
  * - A rook on A1 has 7 squares vertical and 7 squares horizontal movement.
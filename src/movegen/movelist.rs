@@ -60,8 +60,11 @@ impl MoveList {
         }
     }
 
-    // Used to store a move in the move list.
+    // Used to store a move in the move list. The list holds up to
+    // MAX_LEGAL_MOVES moves. No position is known to have more than 218
+    // legal moves, so if the list fills up, the move generator has a bug.
     pub fn push(&mut self, m: Move) {
+        debug_assert!(self.count < MAX_LEGAL_MOVES, "MoveList: capacity exceeded.");
         self.list[self.count as usize] = m;
         self.count += 1;
     }
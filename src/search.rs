@@ -26,21 +26,27 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 mod alpha_beta;
 pub mod defs;
 mod iter_deep;
+mod lmr;
 mod qsearch;
+mod see;
+mod skill;
 mod sorting;
+#[cfg(test)]
+mod tests;
 mod time;
 mod utils;
 
 use crate::{
     board::Board,
+    engine::defs::{AnalysisData, SearchData, TT},
     engine::defs::{ErrFatal, Information},
-    engine::defs::{SearchData, TT},
+    evaluation::pawns::PawnHash,
     movegen::MoveGenerator,
 };
 use crossbeam_channel::Sender;
 use defs::{
     SearchControl, SearchInfo, SearchParams, SearchRefs, SearchReport, SearchSummary,
-    SearchTerminate,
+    SearchTerminate, ANALYSIS_HASH_SIZE,
 };
 use std::{
     sync::{Arc, Mutex},
@@ -81,6 +87,9 @@ impl Search {
             let arc_mg = Arc::clone(&mg);
             let arc_tt = Arc::clone(&tt);
             let mut search_params = SearchParams::new();
+            let mut pawn_hash = PawnHash::new();
+            let mut analysis: Option<TT<AnalysisData>> = None;
+            let lmr_table = Search::lmr_table();
 
             let mut quit = false;
             let mut halt = true;
@@ -111,12 +120,26 @@ impl Search {
                     // Create a place to put search information
                     let mut search_info = SearchInfo::new();
 
+                    // Annotations are only kept in analysis mode. Their
+                    // table is created the first time it is needed.
+                    if search_params.analyse_mode && analysis.is_none() {
+                        analysis = Some(TT::new(ANALYSIS_HASH_SIZE));
+                    }
+                    let analysis_refs = if search_params.analyse_mode {
+                        analysis.as_mut()
+                    } else {
+                        None
+                    };
+
                     // Create references to all needed information and structures.
                     let mut search_refs = SearchRefs {
                         board: &mut board,
                         mg: &arc_mg,
                         tt: &arc_tt,
                         tt_enabled,
+                        pawn_hash: &mut pawn_hash,
+                        analysis: analysis_refs,
+                        lmr_table: &lmr_table,
                         search_params: &mut search_params,
                         search_info: &mut search_info,
                         control_rx: &control_rx,
@@ -124,10 +147,11 @@ impl Search {
                     };
 
                     // Start the search using Iterative Deepening.
-                    let (best_move, terminate) = Search::iterative_deepening(&mut search_refs);
+                    let result = Search::iterative_deepening(&mut search_refs);
+                    let terminate = result.terminate;
 
                     // Inform the engine that the search has finished.
-                    let information = Information::Search(SearchReport::Finished(best_move));
+                    let information = Information::Search(SearchReport::Finished(result));
                     t_report_tx.send(information).expect(ErrFatal::CHANNEL);
 
                     // If the search was finished due to a Stop or Quit
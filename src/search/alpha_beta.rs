@@ -22,7 +22,13 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 use super::{
-    defs::{SearchTerminate, CHECKMATE, CHECK_TERMINATION, DRAW, INF, SEND_STATS, STALEMATE},
+    defs::{
+        NodeReturn, RootMove, SearchTerminate, CHECKMATE, CHECKMATE_THRESHOLD, DRAW,
+        HISTORY_PRUNING_MAX_DEPTH, HISTORY_PRUNING_MIN_MOVES, HISTORY_PRUNING_THRESHOLD, INF,
+        LMR_MIN_DEPTH, LMR_MIN_MOVES, MULTI_CUT_CUTOFFS, MULTI_CUT_MIN_DEPTH, MULTI_CUT_MOVES,
+        MULTI_CUT_REDUCTION, SEND_STATS, STALEMATE, TT_CUTOFF_MAX_HALFMOVES, TT_REDUCTION_MAX_GAP,
+        TT_REDUCTION_MIN_DEPTH,
+    },
     Search, SearchRefs,
 };
 use crate::{
@@ -35,57 +41,114 @@ use crate::{
 
 impl Search {
     pub fn alpha_beta(
+        depth: i8,
+        alpha: i16,
+        beta: i16,
+        pv: &mut Vec<Move>,
+        refs: &mut SearchRefs,
+    ) -> i16 {
+        let (score, reason) = Search::alpha_beta_node(depth, alpha, beta, pv, refs);
+
+        // Log why this node returned, if the engine was compiled with the
+        // "trace" feature. Otherwise this compiles away completely.
+        if cfg!(feature = "trace") {
+            Search::trace_node(depth, alpha, beta, score, reason, refs);
+        }
+
+        score
+    }
+
+    // This is the actual alpha/beta search. Next to the score, it returns
+    // the reason why the node returned, for use by the trace.
+    fn alpha_beta_node(
         mut depth: i8,
         mut alpha: i16,
         beta: i16,
         pv: &mut Vec<Move>,
         refs: &mut SearchRefs,
-    ) -> i16 {
+    ) -> (i16, NodeReturn) {
         let quiet = refs.search_params.quiet; // If quiet, don't send intermediate stats.
         let is_root = refs.search_info.ply == 0; // At root if no moves were played.
-        let mut do_pvs = false; // Used for PVS (Principal Variation Search)
+
+        // At a limited skill level, each root move is searched with a
+        // full window, so it gets an exact score to pick a move from.
+        let score_root_moves = is_root && refs.search_params.is_limited_strength();
+
+        // PV-nodes are searched with an open window; all other nodes are
+        // searched with a null window.
+        let is_pv_node = beta as i32 - alpha as i32 > 1;
+
+        // Nothing below this node has seen a repetition yet. Close to the
+        // 50-move rule, the score depends on the halfmove clock, which the
+        // Zobrist key doesn't include: a score stored at a lower clock may
+        // not be a draw, while this position is. Such a node depends on
+        // the path that led to it from the start.
+        let near_move_rule = refs.board.game_state.halfmove_clock > TT_CUTOFF_MAX_HALFMOVES;
+        refs.search_info.path_dependent = near_move_rule;
 
         // Check if termination condition is met.
-        if refs.search_info.nodes & CHECK_TERMINATION == 0 {
+        if Search::is_checkpoint(refs) {
             Search::check_termination(refs);
         }
 
         // If time is up, abort. This depth won't be considered in
         // iterative deepening as it is unfinished.
         if refs.search_info.terminate != SearchTerminate::Nothing {
-            return 0;
+            return (0, NodeReturn::Aborted);
         }
 
         // Stop going deeper if we hit MAX_PLY.
         if refs.search_info.ply >= MAX_PLY {
-            return evaluation::evaluate_position(refs.board);
+            let eval = evaluation::evaluate_position(refs.board, refs.pawn_hash);
+            return (eval, NodeReturn::MaxPly);
         }
 
         // Determine if we are in check.
-        let is_check = refs.mg.square_attacked(
-            refs.board,
-            refs.board.opponent(),
-            refs.board.king_square(refs.board.us()),
-        );
+        let is_check = refs.board.in_check(refs.mg);
 
         // If so, extend search depth by 1 to determine the best way to get
-        // out of the check before we go into quiescence search.
-        if is_check {
+        // out of the check before we go into quiescence search. The number
+        // of extensions on one path is limited, so long forcing lines can't
+        // make the search explode. When the budget is used up, a position
+        // in check at the horizon goes into quiescence search, which then
+        // searches the evasions.
+        let ply = refs.search_info.ply as usize;
+        let mut extensions = if is_root {
+            0
+        } else {
+            refs.search_info.extensions[ply - 1]
+        };
+        let budget = refs
+            .search_info
+            .depth
+            .saturating_mul(refs.search_params.extension_budget);
+        if is_check && extensions < budget {
             depth += 1;
+            extensions = extensions.saturating_add(1);
         }
+        refs.search_info.extensions[ply] = extensions;
 
         // We have arrived at the leaf node. Evaluate the position and
         // return the result.
         if depth <= 0 {
-            return Search::quiescence(alpha, beta, pv, refs);
+            let eval = Search::quiescence(0, alpha, beta, pv, refs);
+            return (eval, NodeReturn::Quiescence);
         }
 
         // Count this node, as it is not aborted or searched by QSearch.
+        let nodes_at_entry = refs.search_info.nodes;
         refs.search_info.nodes += 1;
 
+        // The killers two plies deeper were found in another part of the
+        // tree. They're unlikely to be useful for this node's grandchildren,
+        // so clear them.
+        Search::clear_killer_moves(refs.search_info.ply + 2, refs);
+
         // Variables to hold TT value and move if any.
         let mut tt_value: Option<i16> = None;
         let mut tt_move: ShortMove = ShortMove::new(0);
+        let mut tt_eval: Option<i16> = None;
+        let mut tt_fail_high = false;
 
         // Probe the TT for information.
         if refs.tt_enabled {
@@ -98,16 +161,51 @@ impl Search {
                 let tt_result = data.get(depth, refs.search_info.ply, alpha, beta);
                 tt_value = tt_result.0;
                 tt_move = tt_result.1;
+                tt_eval = data.static_eval();
+                tt_fail_high = data.shallow_fail_high(depth, beta, TT_REDUCTION_MAX_GAP);
             }
         }
 
-        // If we have a value from the TT, then return immediately.
+        // If we have a value from the TT, then return immediately. Close
+        // to the 50-move rule, the position is searched, and only the TT
+        // move is used.
         if let Some(v) = tt_value {
-            if !is_root {
-                return v;
+            if !is_root && !near_move_rule {
+                return (v, NodeReturn::TtCutoff);
             }
         }
 
+        // A shallower search of this position failed high. That is weak
+        // evidence that this search will fail high as well, so the node
+        // is searched one ply less deep. Only lower bounds do this: a
+        // reduction after a shallow fail-low could hide a move that only
+        // turns out to be good at a greater depth. Mate scores are too
+        // precise to guess at, and in analysis mode, accuracy matters
+        // more than speed.
+        let do_tt_reduction = refs.search_params.tt_reductions
+            && tt_fail_high
+            && !is_root
+            && !refs.search_params.analyse_mode
+            && !is_pv_node
+            && !is_check
+            && depth >= TT_REDUCTION_MIN_DEPTH
+            && beta.abs() < CHECKMATE_THRESHOLD;
+
+        if do_tt_reduction {
+            depth -= 1;
+        }
+
+        // Keep the static evaluation of this position. In check, it is
+        // not a meaningful score, so it is not kept. If the position was
+        // searched before, the evaluation is taken from the TT.
+        let static_eval = if is_check {
+            None
+        } else {
+            tt_eval.or_else(|| Some(evaluation::evaluate_position(refs.board, refs.pawn_hash)))
+        };
+        refs.search_info.static_evals[refs.search_info.ply as usize] = static_eval;
+        let improving = Search::is_improving(refs);
+
         /*=== Actual searching starts here ===*/
 
         // Generate the moves in this position
@@ -119,6 +217,29 @@ impl Search {
         // Do move scoring, so the best move will be searched first.
         Search::score_moves(&mut move_list, tt_move, refs);
 
+        // Multi-cut: in a non-PV node where several moves fail high in a
+        // reduced search, at least one of them will most likely also fail
+        // high in the full search. Then the node can be pruned right away.
+        // Mate scores are too precise to guess at, so don't do this close
+        // to them. In analysis mode, accuracy matters more than speed.
+        let do_multi_cut = !is_root
+            && !refs.search_params.analyse_mode
+            && !is_pv_node
+            && !is_check
+            && depth >= MULTI_CUT_MIN_DEPTH
+            && beta.abs() < CHECKMATE_THRESHOLD;
+
+        if do_multi_cut {
+            if let Some(path_dependent) = Search::multi_cut(depth, beta, &mut move_list, refs) {
+                refs.search_info.path_dependent = near_move_rule || path_dependent;
+                return (beta, NodeReturn::MultiCut);
+            }
+
+            if refs.search_info.interrupted() {
+                return (0, NodeReturn::Aborted);
+            }
+        }
+
         // After SEND_STATS nodes have been searched, check if the
         // MIN_TIME_STATS has been exceeded; if so, sne dthe current
         // statistics to the GUI.
@@ -126,6 +247,11 @@ impl Search {
             Search::send_stats_to_gui(refs);
         }
 
+        // Start collecting the scores of the root moves anew.
+        if score_root_moves {
+            refs.search_info.root_moves.clear();
+        }
+
         // Set the initial best eval_score (to the worst possible value)
         let mut best_eval_score = -INF;
 
@@ -135,6 +261,40 @@ impl Search {
         // Holds the best move in the move loop, for storing into the TT.
         let mut best_move: ShortMove = ShortMove::new(0);
 
+        // If root moves were excluded, the best move found isn't the best
+        // move of the root position, so nothing is saved into the TT.
+        let is_excluding = is_root && !refs.search_info.excluded_root_moves.is_empty();
+
+        // A draw by repetition depends on the moves leading up to this
+        // node. If one was found anywhere below this node, the node's
+        // score depends on that path as well, and could be wrong when the
+        // position is reached by another path. Such a score is not stored
+        // into the TT. The same goes for a node close to the 50-move rule.
+        let mut path_dependent = near_move_rule;
+
+        // Quiet moves searched in this node, to update the history
+        // heuristic with if one of the next moves causes a beta cutoff.
+        let mut quiets_tried = MoveList::new();
+
+        // History leaf pruning: close to the leaves, quiet moves that have
+        // a bad history are not searched. This isn't done at the root or
+        // in the PV, or when in check, because every move matters there.
+        // It also isn't done in analysis mode.
+        let do_history_pruning = !is_root
+            && !refs.search_params.analyse_mode
+            && !is_pv_node
+            && !is_check
+            && depth <= HISTORY_PRUNING_MAX_DEPTH;
+
+        // Moves that give check are not pruned. The check info is computed
+        // once for this node, so each move can be tested cheaply.
+        let check_info = do_history_pruning.then(|| refs.board.check_info(refs.mg));
+
+        // Late move reductions are not done at the root, where the root
+        // moves are scored, or when in check, because then every move
+        // matters.
+        let do_lmr = !is_root && !is_check && depth >= LMR_MIN_DEPTH;
+
         // Iterate over the moves.
         for i in 0..move_list.len() {
             // This function finds the best move to test according to the
@@ -143,6 +303,34 @@ impl Search {
             Search::pick_move(&mut move_list, i);
 
             let current_move = move_list.get_move(i);
+
+            // When searching several lines, the root moves of the lines
+            // that were already found are not searched again.
+            if is_root
+                && refs
+                    .search_info
+                    .excluded_root_moves
+                    .contains(&current_move.to_short_move())
+            {
+                continue;
+            }
+
+            let is_quiet =
+                current_move.captured() == Pieces::NONE && current_move.promoted() == Pieces::NONE;
+
+            // Prune quiet moves with a bad history, if enough moves have
+            // already been searched in this node and they don't give check.
+            if let Some(ci) = &check_info {
+                if is_quiet
+                    && legal_moves_found >= HISTORY_PRUNING_MIN_MOVES
+                    && Search::history_score(current_move, refs)
+                        < HISTORY_PRUNING_THRESHOLD * depth as i32
+                    && !refs.board.gives_check(current_move, ci, refs.mg)
+                {
+                    continue;
+                }
+            }
+
             let is_legal = refs.board.make(current_move, refs.mg);
 
             // If not legal, skip the move and the rest of the function.
@@ -174,8 +362,46 @@ impl Search {
 
             // If it isn't a draw, we must search.
             if !Search::is_draw(refs) {
-                // Try a PVS if applicable.
-                if do_pvs {
+                // Late move reductions: quiet moves late in the move list
+                // are searched with less depth, unless they give check.
+                let reduction = if do_lmr
+                    && is_quiet
+                    && legal_moves_found > LMR_MIN_MOVES
+                    && !refs.board.in_check(refs.mg)
+                {
+                    Search::lmr_reduction(
+                        refs.lmr_table,
+                        depth,
+                        legal_moves_found,
+                        is_pv_node,
+                        improving,
+                    )
+                } else {
+                    0
+                };
+
+                // A reduced move must be searched at full depth after
+                // all, if it turns out to beat alpha.
+                let mut full_depth = true;
+                if reduction > 0 {
+                    eval_score = -Search::alpha_beta(
+                        depth - 1 - reduction,
+                        -alpha - 1,
+                        -alpha,
+                        &mut node_pv,
+                        refs,
+                    );
+                    full_depth = eval_score > alpha;
+                }
+
+                // Try a PVS if applicable: the first move is expected to
+                // be the best, so the next moves are searched with a null
+                // window to prove they are not better. This also holds if
+                // the first move didn't raise alpha, as when the search
+                // fails low on an aspiration window.
+                if score_root_moves {
+                    eval_score = -Search::alpha_beta(depth - 1, -INF, INF, &mut node_pv, refs);
+                } else if full_depth && legal_moves_found > 1 {
                     eval_score =
                         -Search::alpha_beta(depth - 1, -alpha - 1, -alpha, &mut node_pv, refs);
 
@@ -184,15 +410,37 @@ impl Search {
                         eval_score =
                             -Search::alpha_beta(depth - 1, -beta, -alpha, &mut node_pv, refs);
                     }
-                } else {
+                } else if full_depth {
                     eval_score = -Search::alpha_beta(depth - 1, -beta, -alpha, &mut node_pv, refs);
                 }
+
+                path_dependent |= refs.search_info.path_dependent;
+            } else {
+                path_dependent |= Search::is_repetition(refs.board) > 0;
+
+                // Draws are detected before searching the child node, so
+                // log them here, on behalf of that node.
+                if cfg!(feature = "trace") {
+                    Search::trace_node(depth - 1, -beta, -alpha, DRAW, NodeReturn::Draw, refs);
+                }
             }
 
             // Take back the move, and decrease ply accordingly.
             refs.board.unmake();
             refs.search_info.ply -= 1;
 
+            // If the search was terminated while searching this move, its
+            // score is meaningless. Don't store anything into the TT or
+            // the PV, and unwind immediately.
+            if refs.search_info.interrupted() {
+                return (0, NodeReturn::Aborted);
+            }
+
+            if score_root_moves {
+                let root_move = RootMove::new(current_move, eval_score);
+                refs.search_info.root_moves.push(root_move);
+            }
+
             // eval_score is better than the best we found so far, so we
             // save a new best_move that'll go into the hash table.
             if eval_score > best_eval_score {
@@ -203,26 +451,44 @@ impl Search {
             // Beta cutoff: this move is so good for our opponent, that we
             // do not search any further. Insert into TT and return beta.
             if eval_score >= beta {
-                refs.tt.lock().expect(ErrFatal::LOCK).insert(
-                    refs.board.game_state.zobrist_key,
-                    SearchData::create(
+                if !path_dependent && !is_excluding {
+                    refs.tt.lock().expect(ErrFatal::LOCK).insert(
+                        refs.board.game_state.zobrist_key,
+                        SearchData::create(
+                            depth,
+                            refs.search_info.ply,
+                            HashFlag::Beta,
+                            beta,
+                            static_eval,
+                            best_move,
+                        ),
+                    );
+                    Search::annotate(
                         depth,
-                        refs.search_info.ply,
                         HashFlag::Beta,
                         beta,
                         best_move,
-                    ),
-                );
+                        static_eval,
+                        nodes_at_entry,
+                        refs,
+                    );
+                }
+                refs.search_info.path_dependent = path_dependent;
 
                 // If the move is not a capture but still causes a
                 // beta-cutoff, then store it as a killer move and update
                 // the history heuristics.
-                if current_move.captured() == Pieces::NONE {
+                if is_quiet {
                     Search::store_killer_move(current_move, refs);
-                    // Search::update_history_heuristic(current_move, depth, refs);
+                    Search::update_history_heuristic(current_move, &quiets_tried, depth, refs);
                 }
 
-                return beta;
+                return (beta, NodeReturn::BetaCutoff);
+            }
+
+            // Remember quiet moves that didn't cause a beta cutoff.
+            if is_quiet {
+                quiets_tried.push(current_move);
             }
 
             // We found a better move for us.
@@ -234,7 +500,6 @@ impl Search {
                 hash_flag = HashFlag::Exact;
 
                 // Update the Principal Variation.
-                do_pvs = true;
                 pv.clear();
                 pv.push(current_move);
                 pv.append(&mut node_pv);
@@ -247,21 +512,97 @@ impl Search {
             if is_check {
                 // The return value is minus CHECKMATE, because if we have
                 // no legal moves and are in check, it's game over.
-                return -CHECKMATE + (refs.search_info.ply as i16);
+                let mated = -CHECKMATE + (refs.search_info.ply as i16);
+                return (mated, NodeReturn::Checkmate);
             } else {
-                return STALEMATE;
+                return (STALEMATE, NodeReturn::Stalemate);
             }
         }
 
         // We save the best move we found for us; with an ALPHA flag if we
         // didn't improve alpha, or EXACT if we did raise alpha.
-        refs.tt.lock().expect(ErrFatal::LOCK).insert(
-            refs.board.game_state.zobrist_key,
-            SearchData::create(depth, refs.search_info.ply, hash_flag, alpha, best_move),
-        );
+        if !path_dependent && !is_excluding {
+            refs.tt.lock().expect(ErrFatal::LOCK).insert(
+                refs.board.game_state.zobrist_key,
+                SearchData::create(
+                    depth,
+                    refs.search_info.ply,
+                    hash_flag,
+                    alpha,
+                    static_eval,
+                    best_move,
+                ),
+            );
+            Search::annotate(
+                depth,
+                hash_flag,
+                alpha,
+                best_move,
+                static_eval,
+                nodes_at_entry,
+                refs,
+            );
+        }
+        refs.search_info.path_dependent = path_dependent;
 
         // We have traversed the entire move list and found the best
         // possible move/eval_score for us.
-        alpha
+        (alpha, NodeReturn::AllMoves)
+    }
+
+    // Searches the first MULTI_CUT_MOVES legal moves with a reduced depth
+    // and a null window around beta. As soon as MULTI_CUT_CUTOFFS of them
+    // have failed high, it returns whether any of those depended on a
+    // draw by repetition. Returns None if the node can't be cut.
+    fn multi_cut(
+        depth: i8,
+        beta: i16,
+        move_list: &mut MoveList,
+        refs: &mut SearchRefs,
+    ) -> Option<bool> {
+        let mut moves_tried = 0;
+        let mut cutoffs = 0;
+        let mut path_dependent = false;
+        let mut i = 0;
+
+        while i < move_list.len() && moves_tried < MULTI_CUT_MOVES {
+            Search::pick_move(move_list, i);
+            let current_move = move_list.get_move(i);
+            i += 1;
+
+            if !refs.board.make(current_move, refs.mg) {
+                continue;
+            }
+
+            moves_tried += 1;
+            refs.search_info.ply += 1;
+
+            let mut node_pv: Vec<Move> = Vec::new();
+            let reduced_depth = depth - 1 - MULTI_CUT_REDUCTION;
+            let (eval_score, repeated) = if Search::is_draw(refs) {
+                (DRAW, Search::is_repetition(refs.board) > 0)
+            } else {
+                let score =
+                    -Search::alpha_beta(reduced_depth, -beta, -beta + 1, &mut node_pv, refs);
+                (score, refs.search_info.path_dependent)
+            };
+
+            refs.board.unmake();
+            refs.search_info.ply -= 1;
+
+            if refs.search_info.interrupted() {
+                return None;
+            }
+
+            if eval_score >= beta {
+                cutoffs += 1;
+                path_dependent |= repeated;
+                if cutoffs >= MULTI_CUT_CUTOFFS {
+                    return Some(path_dependent);
+                }
+            }
+        }
+
+        None
     }
 }
@@ -1,7 +1,8 @@
 use crate::{
     board::Board,
-    defs::MAX_PLY,
-    engine::defs::{Information, SearchData, TT},
+    defs::{NrOf, Sides, MAX_PLY},
+    engine::defs::{AnalysisData, Information, SearchData, TT},
+    evaluation::pawns::PawnHash,
     movegen::{
         defs::{Move, ShortMove},
         MoveGenerator,
@@ -16,20 +17,53 @@ use std::{
 pub use super::time::OVERHEAD;
 
 pub const INF: i16 = 25_000;
-// pub const ASPIRATION_WINDOW: i16 = 50;
+pub const ASPIRATION_WINDOW: i16 = 50;
 pub const CHECKMATE: i16 = 24_000;
 pub const CHECKMATE_THRESHOLD: i16 = 23_900;
 pub const STALEMATE: i16 = 0;
 pub const DRAW: i16 = 0;
-pub const CHECK_TERMINATION: usize = 0x7FF; // 2.047 nodes
+pub const CHECKPOINT_MIN_INTERVAL: usize = 0x100; // 256 nodes
+pub const CHECKPOINT_MAX_INTERVAL: usize = 0x4000; // 16.384 nodes
+pub const CHECKPOINT_GROWTH: usize = 64; // Interval grows by 1 node per this many searched
 pub const SEND_STATS: usize = 0x7FFFF; // 524.287 nodes
 pub const MIN_TIME_STATS: u128 = 2_000; // Minimum time for sending stats
 pub const MIN_TIME_CURR_MOVE: u128 = 1_000; // Minimum time for sending curr_move
+pub const MIN_THINK_TIME_POLL: u64 = 1; // Msecs between checks while waiting out the minimum
 pub const MAX_KILLER_MOVES: usize = 2;
+pub const EASY_MOVE_ITERATIONS: u8 = 3; // Default iterations the best move must stay the same
+pub const EASY_MOVE_MARGIN: i16 = 150; // Default margin over all other moves, in centipawns
+pub const EASY_MOVE_MIN_DEPTH: i8 = 6; // Only look for an easy move from this depth
+pub const EASY_MOVE_TIME_FACTOR: f64 = 0.3; // Part of the allocated time kept for an easy move
+pub const MAX_SKILL_LEVEL: u8 = 20; // Full strength
+pub const FORTRESS_MIN_ITERATIONS: u8 = 6; // Iterations with an unchanged score
+pub const FORTRESS_MIN_PLIES: u8 = 40; // Plies without captures or pawn moves
+pub const MAX_HISTORY: i32 = 16_384; // History scores stay within +/- this value
+pub const HISTORY_PRUNING_MAX_DEPTH: i8 = 3; // Only prune quiet moves up to this depth
+pub const HISTORY_PRUNING_MIN_MOVES: u8 = 4; // Never prune the first moves
+pub const HISTORY_PRUNING_THRESHOLD: i32 = -1_024; // Per ply of depth
+pub const MULTI_CUT_MIN_DEPTH: i8 = 6; // Only try multi-cut from this depth
+pub const MULTI_CUT_REDUCTION: i8 = 3; // Depth reduction for multi-cut searches
+pub const MULTI_CUT_MOVES: u8 = 6; // Number of moves to try (M)
+pub const MULTI_CUT_CUTOFFS: u8 = 3; // Cutoffs needed to prune the node (C)
+pub const TT_REDUCTION_MIN_DEPTH: i8 = 6; // Only reduce on a shallow TT fail-high from this depth
+pub const TT_REDUCTION_MAX_GAP: i8 = 1; // Plies a TT entry may be shallower to cause a reduction
+pub const ROOT_DROP_MARGIN: i16 = 50; // Score drop that counts against the root best move
+pub const ROOT_UNSTABLE_DROPS: u8 = 2; // Default drops in a row before the TT move loses priority
+pub const QS_MAX_DEPTH: i8 = 16; // Plies of captures searched in quiescence
+pub const LMR_MIN_DEPTH: i8 = 3; // Only reduce late moves from this depth
+pub const LMR_MIN_MOVES: u8 = 3; // Never reduce the first moves
+pub const LMR_TABLE_SIZE: usize = 64; // Depths and move numbers in the reduction table
+pub const EXTENSION_BUDGET: i8 = 1; // Default extensions on one path, per ply of root depth
+pub const ASPIRATION_MIN_DEPTH: i8 = 5; // Default depth to start aspiration windows
+pub const SINGLE_REPLY_DEPTH: i8 = 4; // Depth to search a forced move to, in a game
+pub const TT_CUTOFF_MAX_HALFMOVES: u8 = 90; // No TT cutoffs or stores closer to the 50-move rule
+pub const ANALYSIS_HASH_SIZE: usize = 16; // Megabytes for annotations in analysis mode
 
-pub type SearchResult = (Move, SearchTerminate);
+pub type LmrTable = [[i8; LMR_TABLE_SIZE]; LMR_TABLE_SIZE];
 type KillerMoves = [[ShortMove; MAX_KILLER_MOVES]; MAX_PLY as usize];
-// type HistoryHeuristic = [[[u32; NrOf::SQUARES]; NrOf::PIECE_TYPES]; Sides::BOTH];
+type Extensions = [i8; MAX_PLY as usize];
+type StaticEvals = [Option<i16>; MAX_PLY as usize];
+type HistoryHeuristic = [[[i32; NrOf::SQUARES]; NrOf::PIECE_TYPES]; Sides::BOTH];
 
 #[derive(PartialEq)]
 // These commands can be used by the engine thread to control the search.
@@ -48,6 +82,22 @@ pub enum SearchTerminate {
     Nothing, // No command received yet.
 }
 
+// The reasons why a node in the alpha/beta search can return. These are
+// only used to trace the search, when compiled with the "trace" feature.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum NodeReturn {
+    Aborted,    // Search was terminated; the score is meaningless.
+    MaxPly,     // Maximum ply reached; position was evaluated.
+    Quiescence, // Depth exhausted; score comes from quiescence search.
+    TtCutoff,   // Usable score found in the transposition table.
+    MultiCut,   // Node pruned by multi-cut.
+    BetaCutoff, // A move failed high.
+    AllMoves,   // All moves were searched without a beta cutoff.
+    Checkmate,  // No legal moves, and in check.
+    Stalemate,  // No legal moves, and not in check.
+    Draw,       // Draw by rule, material, or repetition.
+}
+
 // SearchMode lists how the search termination criteria will be evaluated,
 // to see if the search has to be stopped.
 #[derive(PartialEq, Copy, Clone)]
@@ -55,6 +105,7 @@ pub enum SearchMode {
     Depth,    // Run until requested depth is reached.
     MoveTime, // Run until 'time per move' is used up.
     Nodes,    // Run until the number of requested nodes was reached.
+    Mate,     // Run until a mate in the requested number of moves is found.
     GameTime, // Search determines when to quit, depending on available time.
     Infinite, // Run forever, until the 'stop' command is received.
     Nothing,  // No search mode has been defined.
@@ -92,12 +143,24 @@ impl GameTime {
 // before the game starts.)
 #[derive(PartialEq, Copy, Clone)]
 pub struct SearchParams {
-    pub depth: i8,               // Maximum depth to search to
-    pub move_time: u128,         // Maximum time per move to search
-    pub nodes: usize,            // Maximum number of nodes to search
-    pub game_time: GameTime,     // Time available for entire game
-    pub search_mode: SearchMode, // Defines the mode to search in
-    pub quiet: bool,             // No intermediate search stats updates
+    pub depth: i8,                // Maximum depth to search to
+    pub move_time: u128,          // Maximum time per move to search
+    pub nodes: usize,             // Maximum number of nodes to search
+    pub mate: u8,                 // Find a mate in this number of moves
+    pub game_time: GameTime,      // Time available for entire game
+    pub search_mode: SearchMode,  // Defines the mode to search in
+    pub quiet: bool,              // No intermediate search stats updates
+    pub easy_move_iterations: u8, // Iterations with the same best move before an easy move
+    pub easy_move_margin: i16,    // Margin of an easy move over all other moves
+    pub skill_level: u8,          // Playing strength: 0 (weakest) to 20 (full)
+    pub analyse_mode: bool,       // Favor accuracy: no speculative pruning
+    pub multi_pv: u8,             // Number of best lines to search and report
+    pub aspiration_depth: i8,     // Depth from which aspiration windows are used
+    pub tt_reductions: bool,      // Reduce nodes that failed high in a shallower search
+    pub see_ordering: bool,       // Order quiescence captures by SEE
+    pub unstable_root_drops: u8,  // Score drops before the root TT move loses priority (0 = never)
+    pub extension_budget: i8,     // Check extensions on one path, per ply of root depth
+    pub min_think_time: u128,     // Don't return a move before this many msecs (GameTime)
 }
 
 impl SearchParams {
@@ -106,31 +169,60 @@ impl SearchParams {
             depth: MAX_PLY,
             move_time: 0,
             nodes: 0,
+            mate: 0,
             game_time: GameTime::new(0, 0, 0, 0, None),
             search_mode: SearchMode::Nothing,
             quiet: false,
+            easy_move_iterations: EASY_MOVE_ITERATIONS,
+            easy_move_margin: EASY_MOVE_MARGIN,
+            skill_level: MAX_SKILL_LEVEL,
+            analyse_mode: false,
+            multi_pv: 1,
+            aspiration_depth: ASPIRATION_MIN_DEPTH,
+            tt_reductions: true,
+            see_ordering: true,
+            unstable_root_drops: ROOT_UNSTABLE_DROPS,
+            extension_budget: EXTENSION_BUDGET,
+            min_think_time: 0,
         }
     }
 
     pub fn is_game_time(&self) -> bool {
         self.search_mode == SearchMode::GameTime
     }
+
+    pub fn is_mate_search(&self) -> bool {
+        self.search_mode == SearchMode::Mate
+    }
+
+    pub fn is_limited_strength(&self) -> bool {
+        self.skill_level < MAX_SKILL_LEVEL
+    }
 }
 
 // The search function will put all findings collected during the running
 // search into this struct.
 #[derive(PartialEq)]
 pub struct SearchInfo {
-    start_time: Option<Instant>,    // Time the search started
-    pub depth: i8,                  // Depth currently being searched
-    pub seldepth: i8,               // Maximum selective depth reached
-    pub nodes: usize,               // Nodes searched
-    pub ply: i8,                    // Number of plys from the root
-    pub killer_moves: KillerMoves,  // Killer moves (array; see "type" above)
-    pub last_stats_sent: u128,      // When last stats update was sent
-    pub last_curr_move_sent: u128,  // When last current move was sent
-    pub allocated_time: u128,       // Allotted msecs to spend on move
-    pub terminate: SearchTerminate, // Terminate flag
+    start_time: Option<Instant>,             // Time the search started
+    pub depth: i8,                           // Depth currently being searched
+    pub seldepth: i8,                        // Maximum selective depth reached
+    pub nodes: usize,                        // Nodes searched
+    pub tb_hits: usize,                      // Successful tablebase probes
+    pub next_checkpoint: usize,              // Node count of next termination check
+    pub ply: i8,                             // Number of plys from the root
+    pub killer_moves: KillerMoves,           // Killer moves (array; see "type" above)
+    pub history_heuristic: HistoryHeuristic, // History scores for quiet moves
+    pub static_evals: StaticEvals,           // Static evaluation per ply
+    pub extensions: Extensions,              // Extensions on the path to each ply
+    pub root_moves: Vec<RootMove>,           // Root moves with their scores
+    pub excluded_root_moves: Vec<ShortMove>, // Root moves of lines already found
+    pub unstable_root_move: ShortMove,       // Root TT move that keeps dropping in score
+    pub path_dependent: bool,                // Last node's score depends on the path to it
+    pub last_stats_sent: u128,               // When last stats update was sent
+    pub last_curr_move_sent: u128,           // When last current move was sent
+    pub allocated_time: u128,                // Allotted msecs to spend on move
+    pub terminate: SearchTerminate,          // Terminate flag
 }
 
 impl SearchInfo {
@@ -140,8 +232,17 @@ impl SearchInfo {
             depth: 0,
             seldepth: 0,
             nodes: 0,
+            tb_hits: 0,
+            next_checkpoint: CHECKPOINT_MIN_INTERVAL,
             ply: 0,
             killer_moves: [[ShortMove::new(0); MAX_KILLER_MOVES]; MAX_PLY as usize],
+            history_heuristic: [[[0; NrOf::SQUARES]; NrOf::PIECE_TYPES]; Sides::BOTH],
+            static_evals: [None; MAX_PLY as usize],
+            extensions: [0; MAX_PLY as usize],
+            root_moves: Vec::new(),
+            excluded_root_moves: Vec::new(),
+            unstable_root_move: ShortMove::new(0),
+            path_dependent: false,
             last_stats_sent: 0,
             last_curr_move_sent: 0,
             allocated_time: 0,
@@ -166,21 +267,39 @@ impl SearchInfo {
     }
 }
 
+// A move at the root of the search, with the score it received. Root
+// move scores are only collected when playing at a limited skill level;
+// at full strength, only the best move's score is known.
+#[derive(PartialEq, Copy, Clone)]
+pub struct RootMove {
+    pub m: Move,
+    pub score: i16,
+}
+
+impl RootMove {
+    pub fn new(m: Move, score: i16) -> Self {
+        Self { m, score }
+    }
+}
+
 // After each completed depth, iterative deepening summarizes the running
 // search results within this struct before sending it to the engine
 // thread. The engine thread will send it to Comm, which will transform the
 // information into UCI/XBoard/Console output and print it to STDOUT.
 #[derive(PartialEq, Clone)]
 pub struct SearchSummary {
-    pub depth: i8,      // depth reached during search
-    pub seldepth: i8,   // Maximum selective depth reached
-    pub time: u128,     // milliseconds
-    pub cp: i16,        // centipawns score
-    pub mate: u8,       // mate in X moves
-    pub nodes: usize,   // nodes searched
-    pub nps: usize,     // nodes per second
-    pub hash_full: u16, // TT use in permille
-    pub pv: Vec<Move>,  // Principal Variation
+    pub depth: i8,            // depth reached during search
+    pub seldepth: i8,         // Maximum selective depth reached
+    pub time: u128,           // milliseconds
+    pub cp: i16,              // centipawns score
+    pub mate: i16,            // mate in X moves (negative if being mated)
+    pub nodes: usize,         // nodes searched
+    pub nps: usize,           // nodes per second
+    pub tb_hits: usize,       // Successful tablebase probes
+    pub hash_full: u16,       // TT use in permille
+    pub pv: Vec<Move>,        // Principal Variation
+    pub multi_pv: Option<u8>, // Line number, if several lines are searched
+    pub bound: ScoreBound,    // Tells if the score is exact or a bound
 }
 
 impl SearchSummary {
@@ -194,6 +313,34 @@ impl SearchSummary {
     }
 }
 
+// A score is exact if it landed inside the search window. If the search
+// failed high on the aspiration window, the real score is at least the
+// reported one (a lower bound); if it failed low, the real score is at
+// most the reported one (an upper bound).
+#[derive(PartialEq, Copy, Clone)]
+pub enum ScoreBound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+// When the search is done, iterative deepening returns the results of
+// the last completed depth in this struct. Because the search can be
+// interrupted halfway through a depth, the node count, selective depth
+// and time cover the entire search, including an unfinished depth.
+#[derive(PartialEq, Clone)]
+pub struct SearchResult {
+    pub best_move: Move,            // Move to play
+    pub ponder_move: Option<Move>,  // Expected reply to the best move
+    pub score: i16,                 // Score of the best move
+    pub depth: i8,                  // Last completed depth
+    pub seldepth: i8,               // Maximum selective depth reached
+    pub nodes: usize,               // Nodes searched
+    pub pv: Vec<Move>,              // Principal Variation, starting with the best move
+    pub time: u128,                 // milliseconds
+    pub terminate: SearchTerminate, // Reason the search was terminated
+}
+
 #[derive(PartialEq, Copy, Clone)]
 // This struct holds the currently searched move, and its move number in
 // the list of legal moves. This struct is sent through the engine thread
@@ -219,15 +366,17 @@ pub struct SearchStats {
     pub time: u128,     // Time spent searching
     pub nodes: usize,   // Number of nodes searched
     pub nps: usize,     // Speed in nodes per second
+    pub tb_hits: usize, // Successful tablebase probes
     pub hash_full: u16, // TT full in permille
 }
 
 impl SearchStats {
-    pub fn new(time: u128, nodes: usize, nps: usize, hash_full: u16) -> Self {
+    pub fn new(time: u128, nodes: usize, nps: usize, tb_hits: usize, hash_full: u16) -> Self {
         Self {
             time,
             nodes,
             nps,
+            tb_hits,
             hash_full,
         }
     }
@@ -245,6 +394,9 @@ pub struct SearchRefs<'a> {
     pub mg: &'a Arc<MoveGenerator>,
     pub tt: &'a Arc<Mutex<TT<SearchData>>>,
     pub tt_enabled: bool,
+    pub pawn_hash: &'a mut PawnHash,
+    pub analysis: Option<&'a mut TT<AnalysisData>>,
+    pub lmr_table: &'a LmrTable,
     pub search_params: &'a mut SearchParams,
     pub search_info: &'a mut SearchInfo,
     pub control_rx: &'a Receiver<SearchControl>,
@@ -254,7 +406,7 @@ pub struct SearchRefs<'a> {
 // This struct holds all the reports a search can send to the engine.
 #[derive(PartialEq)]
 pub enum SearchReport {
-    Finished(Move),                       // Search done. Contains the best move.
+    Finished(SearchResult),               // Search done. Contains the results.
     SearchSummary(SearchSummary),         // Periodic intermediate results.
     SearchCurrentMove(SearchCurrentMove), // Move currently searched.
     SearchStats(SearchStats),             // General search statistics
@@ -22,10 +22,20 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 use super::{
-    defs::{SearchMode, SearchRefs, SearchResult, INF},
+    defs::{
+        RootMove, ScoreBound, SearchMode, SearchRefs, SearchResult, SearchTerminate,
+        ASPIRATION_WINDOW, CHECKMATE, CHECKMATE_THRESHOLD, DRAW, EASY_MOVE_MIN_DEPTH,
+        EASY_MOVE_TIME_FACTOR, INF, MIN_THINK_TIME_POLL, ROOT_DROP_MARGIN, SINGLE_REPLY_DEPTH,
+        STALEMATE,
+    },
     ErrFatal, Information, Search, SearchReport, SearchSummary,
 };
-use crate::{defs::MAX_PLY, movegen::defs::Move};
+use crate::{
+    defs::MAX_PLY,
+    movegen::defs::{Move, MoveList, MoveType, ShortMove},
+};
+use rand::{rngs::SmallRng, SeedableRng};
+use std::{thread, time::Duration};
 
 // Actual search routines.
 impl Search {
@@ -34,8 +44,60 @@ impl Search {
         let mut depth = 1;
         let mut best_move = Move::new(0);
         let mut root_pv: Vec<Move> = Vec::new();
+        let mut best_pv: Vec<Move> = Vec::new();
+        let mut completed_depth = 0;
+        let mut root_moves: Vec<RootMove> = Vec::new();
         let mut stop = false;
+        let mut stable_best_move: u8 = 0;
+        let mut easy_move = false;
+        let mut last_eval: Option<i16> = None;
+        let mut stable_iterations: u8 = 0;
+        let mut root_drops: u8 = 0;
+        let mut best_move_changes: f64 = 0.0;
         let is_game_time = refs.search_params.is_game_time();
+        let is_mate_search = refs.search_params.is_mate_search();
+        let is_limited_strength = refs.search_params.is_limited_strength();
+        let skill_level = refs.search_params.skill_level;
+        let legal_moves = refs.board.legal_moves(refs.mg);
+
+        // If there are no legal moves, the game is already over. There is
+        // nothing to search, and no move to play.
+        if legal_moves.len() == 0 {
+            let score = if refs.board.in_check(refs.mg) {
+                -CHECKMATE
+            } else {
+                STALEMATE
+            };
+
+            return SearchResult {
+                best_move: Move::new(0),
+                ponder_move: None,
+                score,
+                depth: 0,
+                seldepth: 0,
+                nodes: 0,
+                pv: Vec::new(),
+                time: 0,
+                terminate: refs.search_info.terminate,
+            };
+        }
+
+        // With only one legal move, there is no choice to make. When
+        // playing a game, don't spend time on it; a shallow search is
+        // only done to find a score and an expected reply.
+        if is_game_time && legal_moves.len() == 1 {
+            refs.search_params.depth = refs.search_params.depth.min(SINGLE_REPLY_DEPTH);
+        }
+
+        // Number of lines to search. There can't be more lines than legal
+        // moves. At a limited skill level, the root moves are all scored
+        // in one search, so then only one line is searched.
+        let lines = if refs.search_params.multi_pv > 1 && !is_limited_strength {
+            refs.search_params.multi_pv.min(legal_moves.len())
+        } else {
+            1
+        };
+        let first_line = if lines > 1 { Some(1) } else { None };
 
         // Determine available time in case of GameTime search mode.
         if is_game_time {
@@ -60,10 +122,30 @@ impl Search {
             }
         }
 
-        // Set the starting values for alpha and beta, for use with the
-        // aspiration window. We always start with a fully open window.
-        let alpha: i16 = -INF;
-        let beta: i16 = INF;
+        // Some GUIs don't like a move that comes in right away. In a game,
+        // don't return before the minimum think time has passed, unless
+        // that is more than the time allotted for this move.
+        let min_think_time = if is_game_time {
+            refs.search_params
+                .min_think_time
+                .min(refs.search_info.allocated_time)
+        } else {
+            0
+        };
+
+        // A mate in N moves is found within 2N - 1 plies. Limit the depth
+        // of the search to this number of plies.
+        if is_mate_search {
+            let plies = (refs.search_params.mate as i16) * 2 - 1;
+            refs.search_params.depth = plies.min(MAX_PLY as i16) as i8;
+        }
+
+        // When playing at a limited skill level, don't search deeper than
+        // that level allows.
+        if is_limited_strength {
+            let skill_depth = Search::skill_depth(skill_level);
+            refs.search_params.depth = refs.search_params.depth.min(skill_depth);
+        }
 
         // Start the search
         refs.search_info.timer_start();
@@ -71,36 +153,121 @@ impl Search {
             // Set the current depth
             refs.search_info.depth = depth;
 
-            // Get the evaluation for this depth.
-            let eval = Search::alpha_beta(depth, alpha, beta, &mut root_pv, refs);
+            // Get the evaluation for this depth. Once the score is stable
+            // enough (from the aspiration depth in the search parameters),
+            // search with an aspiration window around the score of the
+            // previous depth. Mate scores are exact to the ply, and at
+            // a limited skill level all root moves need an exact score, so
+            // then the window is always fully open.
+            let eval = match last_eval {
+                Some(last)
+                    if depth >= refs.search_params.aspiration_depth
+                        && !is_limited_strength
+                        && last.abs() < CHECKMATE_THRESHOLD =>
+                {
+                    Search::aspiration_search(depth, last, &mut root_pv, first_line, refs)
+                }
+                _ => Search::alpha_beta(depth, -INF, INF, &mut root_pv, refs),
+            };
 
             // Create summary if search was not interrupted.
             if !refs.search_info.interrupted() {
-                // Save the best move until now.
+                // Count the iterations in a row in which the best move
+                // stayed the same, but its score dropped significantly.
+                let same_move =
+                    !root_pv.is_empty() && root_pv[0].get_move() == best_move.get_move();
+                let dropped = last_eval.is_some_and(|last| eval < last - ROOT_DROP_MARGIN);
+                if same_move && dropped {
+                    root_drops = root_drops.saturating_add(1);
+                } else {
+                    root_drops = 0;
+                }
+
+                // Keep track of how often the best move changes. The first
+                // iteration doesn't count, because there was no best move.
+                let changed = depth > 1 && !root_pv.is_empty() && !same_move;
+                best_move_changes = Search::update_best_move_changes(best_move_changes, changed);
+
+                // Save the best move and the PV until now, and count the
+                // iterations in a row that the best move stayed the same.
                 if !root_pv.is_empty() {
+                    stable_best_move = if same_move {
+                        stable_best_move.saturating_add(1)
+                    } else {
+                        0
+                    };
                     best_move = root_pv[0];
+                    best_pv = root_pv.clone();
                 }
+                completed_depth = depth;
+
+                // If the best move keeps dropping, it is not trusted as
+                // the TT move at the root in the next iteration. It is
+                // then sorted like any other move, so another move can
+                // set the bound it has to beat.
+                let drops_needed = refs.search_params.unstable_root_drops;
+                refs.search_info.unstable_root_move =
+                    if drops_needed > 0 && root_drops >= drops_needed {
+                        best_move.to_short_move()
+                    } else {
+                        ShortMove::new(0)
+                    };
+
+                // Keep the root move scores of this completed depth.
+                root_moves = refs.search_info.root_moves.clone();
 
                 // Create search summary for this depth.
-                let elapsed = refs.search_info.timer_elapsed();
-                let nodes = refs.search_info.nodes;
-                let hash_full = refs.tt.lock().expect(ErrFatal::LOCK).hash_full();
-                let summary = SearchSummary {
+                let mate = Search::moves_to_mate(eval);
+
+                // Keep track of how many iterations in a row returned the
+                // same score, for the fortress heuristic.
+                if last_eval == Some(eval) {
+                    stable_iterations = stable_iterations.saturating_add(1);
+                } else {
+                    stable_iterations = 0;
+                }
+                last_eval = Some(eval);
+                let cp = Search::fortress_score(eval, stable_iterations, &root_pv, refs.board);
+
+                Search::send_summary(
                     depth,
-                    seldepth: refs.search_info.seldepth,
-                    time: elapsed,
-                    cp: eval,
-                    mate: 0,
-                    nodes,
-                    nps: Search::nodes_per_second(nodes, elapsed),
-                    hash_full,
-                    pv: root_pv.clone(),
-                };
-
-                // Create information for the engine
-                let report = SearchReport::SearchSummary(summary);
-                let information = Information::Search(report);
-                refs.report_tx.send(information).expect(ErrFatal::CHANNEL);
+                    cp,
+                    mate,
+                    &root_pv,
+                    first_line,
+                    ScoreBound::Exact,
+                    refs,
+                );
+
+                // Search and report the next best lines, if requested.
+                if lines > 1 && !root_pv.is_empty() {
+                    Search::search_other_lines(depth, lines, root_pv[0], refs);
+                }
+
+                // Easy move: if the best move stayed the same for a number
+                // of iterations, and all the other moves are clearly
+                // worse, there is not much to think about. Then only a
+                // part of the allocated time is used. In analysis mode,
+                // the engine never cuts its thinking time short.
+                let look_for_easy_move = is_game_time
+                    && !easy_move
+                    && !refs.search_params.analyse_mode
+                    && depth >= EASY_MOVE_MIN_DEPTH
+                    && stable_best_move >= refs.search_params.easy_move_iterations
+                    && eval.abs() < CHECKMATE_THRESHOLD;
+
+                if look_for_easy_move && Search::is_easy_move(depth, eval, best_move, refs) {
+                    easy_move = true;
+                    let allocated = refs.search_info.allocated_time as f64;
+                    refs.search_info.allocated_time =
+                        (allocated * EASY_MOVE_TIME_FACTOR).round() as u128;
+                }
+
+                // When searching for a mate, stop as soon as we found one
+                // within the requested number of moves.
+                if is_mate_search && mate > 0 {
+                    stop = mate <= refs.search_params.mate as i16;
+                }
 
                 // Search one ply deepr.
                 depth += 1;
@@ -108,17 +275,314 @@ impl Search {
 
             // Determine if time is up, when in GameTime mode.
             let time_up = if is_game_time {
-                refs.search_info.timer_elapsed() > refs.search_info.allocated_time
+                Search::soft_time_up(refs, best_move_changes)
+                    && refs.search_info.timer_elapsed() >= min_think_time
             } else {
                 false
             };
 
             // Stop deepening the search if the current depth was
             // interrupted, or if the time is up.
-            stop = refs.search_info.interrupted() || time_up;
+            stop = stop || refs.search_info.interrupted() || time_up;
+        }
+
+        // If the search finished before the minimum think time, for
+        // example because there was only one legal move, wait out the
+        // rest. A stop or quit command still ends the search right away.
+        while refs.search_info.terminate == SearchTerminate::Nothing
+            && refs.search_info.timer_elapsed() < min_think_time
+        {
+            thread::sleep(Duration::from_millis(MIN_THINK_TIME_POLL));
+            Search::check_termination(refs);
+        }
+
+        let mut score = last_eval.unwrap_or(0);
+
+        // At a limited skill level, the move to play is picked from the
+        // root moves, and may not be the best one. If it isn't, the PV
+        // found by the search doesn't belong to it.
+        if is_limited_strength {
+            let mut rng = SmallRng::from_entropy();
+            if let Some(m) = Search::pick_skill_move(&root_moves, skill_level, &mut rng) {
+                if m.get_move() != best_move.get_move() {
+                    best_move = m;
+                    best_pv = vec![m];
+                    if let Some(rm) = root_moves.iter().find(|rm| rm.m.get_move() == m.get_move()) {
+                        score = rm.score;
+                    }
+                }
+            }
+        }
+
+        // The best move must be legal in the root position. It can only
+        // be missing if the search was stopped before the first depth was
+        // completed. In either case, play the first legal move instead of
+        // sending a move the GUI will reject.
+        let is_legal = (0..legal_moves.len())
+            .any(|i| legal_moves.get_move(i).get_move() == best_move.get_move());
+        debug_assert!(
+            is_legal || best_move.get_move() == 0,
+            "Search: best move is illegal in the root position."
+        );
+        if !is_legal && legal_moves.len() > 0 {
+            best_move = legal_moves.get_move(0);
+            best_pv = vec![best_move];
+        }
+
+        // Search is done. Report the results and the reason to terminate.
+        SearchResult {
+            best_move,
+            ponder_move: best_pv.get(1).copied(),
+            score,
+            depth: completed_depth,
+            seldepth: refs.search_info.seldepth,
+            nodes: refs.search_info.nodes,
+            pv: best_pv,
+            time: refs.search_info.timer_elapsed(),
+            terminate: refs.search_info.terminate,
+        }
+    }
+
+    // Searches all the root moves except the best one, at half the depth,
+    // with a null window at the easy move margin below the best move's
+    // score. If none of them reaches it, the best move is an easy move.
+    fn is_easy_move(depth: i8, eval: i16, best_move: Move, refs: &mut SearchRefs) -> bool {
+        let beta = eval - refs.search_params.easy_move_margin;
+        let mut move_list = MoveList::new();
+        refs.mg
+            .generate_moves(refs.board, &mut move_list, MoveType::All);
+
+        for i in 0..move_list.len() {
+            let current_move = move_list.get_move(i);
+            let is_best_move = current_move.to_short_move() == best_move.to_short_move();
+            if is_best_move || !refs.board.make(current_move, refs.mg) {
+                continue;
+            }
+
+            refs.search_info.ply += 1;
+
+            let mut node_pv: Vec<Move> = Vec::new();
+            let eval_score = if Search::is_draw(refs) {
+                DRAW
+            } else {
+                -Search::alpha_beta(depth / 2 - 1, -beta, -beta + 1, &mut node_pv, refs)
+            };
+
+            refs.board.unmake();
+            refs.search_info.ply -= 1;
+
+            if refs.search_info.interrupted() || eval_score >= beta {
+                return false;
+            }
         }
 
-        // Search is done. Report best move and reason to terminate.
-        (best_move, refs.search_info.terminate)
+        true
+    }
+
+    // Searches the given depth with a window around the score of the
+    // previous depth. If the score falls outside of the window, it is only
+    // a bound. The bound is reported, and the depth is searched again with
+    // the window widened on the side that failed, until the score lands
+    // inside. The PV is only replaced by that of the exact search.
+    fn aspiration_search(
+        depth: i8,
+        last_eval: i16,
+        pv: &mut Vec<Move>,
+        line: Option<u8>,
+        refs: &mut SearchRefs,
+    ) -> i16 {
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = last_eval.saturating_sub(delta).max(-INF);
+        let mut beta = last_eval.saturating_add(delta).min(INF);
+
+        loop {
+            let mut attempt_pv: Vec<Move> = Vec::new();
+            let eval = Search::alpha_beta(depth, alpha, beta, &mut attempt_pv, refs);
+
+            if refs.search_info.interrupted() {
+                return eval;
+            }
+
+            // A side of the window that is fully open can't fail.
+            let bound = if eval <= alpha && alpha > -INF {
+                ScoreBound::Upper
+            } else if eval >= beta && beta < INF {
+                ScoreBound::Lower
+            } else {
+                *pv = attempt_pv;
+                return eval;
+            };
+
+            // A move that raised alpha before the search failed starts a
+            // new PV. If there is none, report the PV of the last depth.
+            let mate = Search::moves_to_mate(eval);
+            let bound_pv = if attempt_pv.is_empty() {
+                &*pv
+            } else {
+                &attempt_pv
+            };
+            Search::send_summary(depth, eval, mate, bound_pv, line, bound, refs);
+
+            delta = delta.saturating_mul(2);
+            if bound == ScoreBound::Upper {
+                alpha = last_eval.saturating_sub(delta).max(-INF);
+            } else {
+                beta = last_eval.saturating_add(delta).min(INF);
+            }
+        }
+    }
+
+    // Searches the lines after the first one at the given depth, by
+    // excluding the root moves of the lines found before. Each line is
+    // reported as soon as it is found. The TT is shared by all lines, so
+    // the next lines profit from the work done for the previous ones.
+    fn search_other_lines(depth: i8, lines: u8, first_move: Move, refs: &mut SearchRefs) {
+        refs.search_info
+            .excluded_root_moves
+            .push(first_move.to_short_move());
+
+        for line in 2..=lines {
+            let mut line_pv: Vec<Move> = Vec::new();
+            let eval = Search::alpha_beta(depth, -INF, INF, &mut line_pv, refs);
+
+            if refs.search_info.interrupted() || line_pv.is_empty() {
+                break;
+            }
+
+            refs.search_info
+                .excluded_root_moves
+                .push(line_pv[0].to_short_move());
+
+            let mate = Search::moves_to_mate(eval);
+            Search::send_summary(
+                depth,
+                eval,
+                mate,
+                &line_pv,
+                Some(line),
+                ScoreBound::Exact,
+                refs,
+            );
+        }
+
+        refs.search_info.excluded_root_moves.clear();
+    }
+
+    // Sends the summary of a line found at the given depth to the engine.
+    fn send_summary(
+        depth: i8,
+        cp: i16,
+        mate: i16,
+        pv: &[Move],
+        multi_pv: Option<u8>,
+        bound: ScoreBound,
+        refs: &mut SearchRefs,
+    ) {
+        let elapsed = refs.search_info.timer_elapsed();
+        let nodes = refs.search_info.nodes;
+        let hash_full = refs.tt.lock().expect(ErrFatal::LOCK).hash_full();
+
+        let summary = SearchSummary {
+            depth,
+            seldepth: refs.search_info.seldepth,
+            time: elapsed,
+            cp,
+            mate,
+            nodes,
+            nps: Search::nodes_per_second(nodes, elapsed),
+            tb_hits: refs.search_info.tb_hits,
+            hash_full,
+            pv: pv.to_vec(),
+            multi_pv,
+            bound,
+        };
+
+        // Create information for the engine
+        let report = SearchReport::SearchSummary(summary);
+        let information = Information::Search(report);
+        refs.report_tx.send(information).expect(ErrFatal::CHANNEL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{
+        defs::ASPIRATION_MIN_DEPTH,
+        tests::{TestSearch, QUIET},
+    };
+
+    const DEPTH: i8 = 5;
+
+    // Searches QUIET with an aspiration window around the given score.
+    // Returns the score and the bounds that were reported on the way.
+    fn aspiration(search: &mut TestSearch, last_eval: i16) -> (i16, Vec<(i16, ScoreBound)>) {
+        let eval = search.with_refs(|refs| {
+            refs.search_info.timer_start();
+            Search::aspiration_search(DEPTH, last_eval, &mut Vec::new(), None, refs)
+        });
+        let bounds = search.summaries().iter().map(|s| (s.cp, s.bound)).collect();
+        (eval, bounds)
+    }
+
+    #[test]
+    fn aspiration_reports_bounds_until_the_score_settles() {
+        let mut search = TestSearch::new(QUIET, DEPTH);
+        let exact = search.with_refs(|refs| {
+            refs.search_info.timer_start();
+            Search::alpha_beta(DEPTH, -INF, INF, &mut Vec::new(), refs)
+        });
+
+        // A window around the right score: the score is exact, and no
+        // bound is reported.
+        let (eval, bounds) = aspiration(&mut search, exact);
+        assert_eq!(eval, exact);
+        assert!(bounds.is_empty());
+
+        // The score is far above the window: it fails high, and is reported
+        // as a lower bound until the window has grown enough.
+        let (eval, bounds) = aspiration(&mut search, exact - 4 * ASPIRATION_WINDOW);
+        assert_eq!(eval, exact);
+        assert!(!bounds.is_empty());
+        for (cp, bound) in bounds {
+            assert!(bound == ScoreBound::Lower && cp <= exact);
+        }
+
+        // The score is far below the window: it fails low, with upper
+        // bounds.
+        let (eval, bounds) = aspiration(&mut search, exact + 4 * ASPIRATION_WINDOW);
+        assert_eq!(eval, exact);
+        assert!(!bounds.is_empty());
+        for (cp, bound) in bounds {
+            assert!(bound == ScoreBound::Upper && cp >= exact);
+        }
+    }
+
+    #[test]
+    fn no_bounds_are_reported_below_the_aspiration_depth() {
+        // In this position, the search at depth 7 fails low on the window
+        // around the score of depth 6.
+        const KIWIPETE: &str =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        const DEPTH: i8 = 7;
+        let bounds = |aspiration_depth: i8| {
+            let mut search = TestSearch::new(KIWIPETE, DEPTH);
+            search.params.aspiration_depth = aspiration_depth;
+            search.run();
+            search
+                .summaries()
+                .iter()
+                .map(|s| (s.depth, s.bound))
+                .collect::<Vec<_>>()
+        };
+
+        let with_window = bounds(ASPIRATION_MIN_DEPTH);
+        assert!(with_window.contains(&(DEPTH, ScoreBound::Upper)));
+
+        // Without a window, each depth is reported once, with its exact
+        // score.
+        let without_window = bounds(DEPTH + 1);
+        let exact: Vec<_> = (1..=DEPTH).map(|d| (d, ScoreBound::Exact)).collect();
+        assert!(without_window == exact);
     }
 }
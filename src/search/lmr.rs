@@ -0,0 +1,134 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// lmr.rs implements Late Move Reductions. Moves sorted late in the move
+// list are unlikely to be the best move, so they are searched with less
+// depth. If such a reduced search does beat alpha, the move is searched
+// again at full depth.
+
+use super::{
+    defs::{LmrTable, LMR_TABLE_SIZE},
+    Search,
+};
+
+// Constants of the base reduction formula: the reduction grows with the
+// logarithm of both the depth and the move number.
+const LMR_BASE: f64 = 0.75;
+const LMR_DIVISOR: f64 = 2.25;
+
+impl Search {
+    // Computes the base reductions for each depth and move number.
+    pub fn lmr_table() -> LmrTable {
+        let mut table: LmrTable = [[0; LMR_TABLE_SIZE]; LMR_TABLE_SIZE];
+
+        for (depth, reductions) in table.iter_mut().enumerate().skip(1) {
+            for (move_number, r) in reductions.iter_mut().enumerate().skip(1) {
+                let d = (depth as f64).ln();
+                let m = (move_number as f64).ln();
+                *r = (LMR_BASE + d * m / LMR_DIVISOR) as i8;
+            }
+        }
+
+        table
+    }
+
+    // Returns the number of plies to reduce the search of a move by. PV
+    // nodes are reduced less, because their result matters most. If the
+    // side to move is not improving its position, its late moves are even
+    // less likely to be any good, so they are reduced more. The reduced
+    // search always has at least one ply left.
+    pub fn lmr_reduction(
+        table: &LmrTable,
+        depth: i8,
+        move_number: u8,
+        is_pv: bool,
+        improving: bool,
+    ) -> i8 {
+        let d = (depth.max(0) as usize).min(LMR_TABLE_SIZE - 1);
+        let m = (move_number as usize).min(LMR_TABLE_SIZE - 1);
+        let mut reduction = table[d][m];
+
+        if is_pv {
+            reduction -= 1;
+        }
+
+        if !improving {
+            reduction += 1;
+        }
+
+        reduction.clamp(0, (depth - 2).max(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pv_and_improving_reduce_less() {
+        let table = Search::lmr_table();
+
+        for depth in 3..30 {
+            for move_number in 1..60 {
+                let r = |is_pv, improving| {
+                    Search::lmr_reduction(&table, depth, move_number, is_pv, improving)
+                };
+                assert!(r(true, false) <= r(false, false));
+                assert!(r(false, true) <= r(false, false));
+                assert!(r(true, true) <= r(true, false));
+                assert!(r(true, true) <= r(false, true));
+            }
+        }
+
+        // Deep enough and late enough, the difference shows.
+        let r = |is_pv, improving| Search::lmr_reduction(&table, 12, 20, is_pv, improving);
+        assert!(r(true, false) < r(false, false));
+        assert!(r(false, true) < r(false, false));
+        assert!(r(true, true) < r(false, false));
+    }
+
+    #[test]
+    fn reduction_grows_with_depth_and_move_number() {
+        let table = Search::lmr_table();
+
+        for depth in 1..LMR_TABLE_SIZE {
+            for move_number in 1..LMR_TABLE_SIZE {
+                assert!(table[depth][move_number] >= table[depth - 1][move_number]);
+                assert!(table[depth][move_number] >= table[depth][move_number - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn reduced_search_keeps_one_ply() {
+        let table = Search::lmr_table();
+
+        for depth in 0..(LMR_TABLE_SIZE as i8 + 10) {
+            for move_number in [1, 4, 10, 40, 63, 200] {
+                let r = Search::lmr_reduction(&table, depth, move_number, false, false);
+                assert!(r >= 0);
+                assert!(depth - 1 - r >= 1 || r == 0);
+            }
+        }
+    }
+}
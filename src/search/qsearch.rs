@@ -22,7 +22,7 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 ======================================================================= */
 
 use super::{
-    defs::{SearchTerminate, CHECK_TERMINATION, SEND_STATS},
+    defs::{SearchTerminate, CHECKMATE, QS_MAX_DEPTH, SEND_STATS},
     Search, SearchRefs,
 };
 use crate::{
@@ -32,7 +32,15 @@ use crate::{
 };
 
 impl Search {
-    pub fn quiescence(mut alpha: i16, beta: i16, pv: &mut Vec<Move>, refs: &mut SearchRefs) -> i16 {
+    // Searches captures until the position is quiet. The qs_depth is the
+    // number of plies searched in quiescence so far.
+    pub fn quiescence(
+        qs_depth: i8,
+        mut alpha: i16,
+        beta: i16,
+        pv: &mut Vec<Move>,
+        refs: &mut SearchRefs,
+    ) -> i16 {
         // We created a new node which we'll search, so count it.
         refs.search_info.nodes += 1;
 
@@ -40,7 +48,7 @@ impl Search {
         let quiet = refs.search_params.quiet;
 
         // Check if search needs to be terminated.
-        if refs.search_info.nodes & CHECK_TERMINATION == 0 {
+        if Search::is_checkpoint(refs) {
             Search::check_termination(refs);
         }
 
@@ -49,25 +57,35 @@ impl Search {
             return 0;
         }
 
-        // Immediately evaluate and return on reaching MAX_PLY
-        if refs.search_info.ply >= MAX_PLY {
-            return evaluation::evaluate_position(refs.board);
+        // Immediately evaluate and return on reaching MAX_PLY, or when
+        // quiescence has searched as deep as it is allowed to. This bounds
+        // the length of capture sequences, so the search always ends.
+        if refs.search_info.ply >= MAX_PLY || qs_depth >= QS_MAX_DEPTH {
+            return evaluation::evaluate_position(refs.board, refs.pawn_hash);
         }
 
+        // Alpha-beta sends a position in check here when the path to it
+        // has used up its check extensions. Then there is no stand-pat:
+        // all evasions are searched, not only the captures. Deeper into
+        // quiescence, checks are not looked at.
+        let in_check = qs_depth == 0 && refs.board.in_check(refs.mg);
+
         // Do a stand-pat here: Check how we're doing, even before we make
         // a move. If the evaluation score is larger than beta, then we're
         // already so bad we don't need to search any further. Just return
         // the beta score.
-        let eval_score = evaluation::evaluate_position(refs.board);
-        if eval_score >= beta {
-            return beta;
-        }
+        if !in_check {
+            let eval_score = evaluation::evaluate_position(refs.board, refs.pawn_hash);
+            if eval_score >= beta {
+                return beta;
+            }
 
-        // If the evaluation score is bigger than alpha, then we can
-        // improve our position. So set alpha to this score and keep
-        // searching until there are no more captures.
-        if eval_score > alpha {
-            alpha = eval_score
+            // If the evaluation score is bigger than alpha, then we can
+            // improve our position. So set alpha to this score and keep
+            // searching until there are no more captures.
+            if eval_score > alpha {
+                alpha = eval_score
+            }
         }
 
         // Stand-pat is done. Start searching the captures in our position.
@@ -76,13 +94,22 @@ impl Search {
         // the recursion, or until there are no more captures available.
         // Then the function will return after looping the move list.
 
-        // Generate only capture moves.
+        // Generate only capture moves, or all moves when in check.
         let mut move_list = MoveList::new();
-        let mtc = MoveType::Capture;
+        let mtc = if in_check {
+            MoveType::All
+        } else {
+            MoveType::Capture
+        };
         refs.mg.generate_moves(refs.board, &mut move_list, mtc);
 
-        // Do move scoring, so the best move will be searched first.
-        Search::score_moves(&mut move_list, ShortMove::new(0), refs);
+        // Do move scoring, so the best capture will be searched first.
+        // Without SEE ordering, the captures are ordered by MVV-LVA only.
+        if in_check || !refs.search_params.see_ordering {
+            Search::score_moves(&mut move_list, ShortMove::new(0), refs);
+        } else {
+            Search::score_captures(&mut move_list, refs);
+        }
 
         // Update search stats in the GUI. Check every SEND_STATS nodes if
         // the minium MIN_TIME_STATS has elapsed before sending.
@@ -91,6 +118,7 @@ impl Search {
         }
 
         // Iterate over the capture moves.
+        let mut legal_moves_found = 0;
         for i in 0..move_list.len() {
             // Pick the next moves with the higest score.
             Search::pick_move(&mut move_list, i);
@@ -102,6 +130,7 @@ impl Search {
             if !is_legal {
                 continue;
             }
+            legal_moves_found += 1;
 
             // Move is legal; increase the ply count.
             refs.search_info.ply += 1;
@@ -115,12 +144,17 @@ impl Search {
             let mut node_pv: Vec<Move> = Vec::new();
 
             // The position is not yet quiet. Go one ply deeper.
-            let eval_score = -Search::quiescence(-beta, -alpha, &mut node_pv, refs);
+            let eval_score = -Search::quiescence(qs_depth + 1, -beta, -alpha, &mut node_pv, refs);
 
             // Take back the move, and decrease ply accordingly.
             refs.board.unmake();
             refs.search_info.ply -= 1;
 
+            // Unwind immediately if the search was terminated.
+            if refs.search_info.interrupted() {
+                return 0;
+            }
+
             // If we are worse than beta (the opponent), then stop
             // searching, because we can't improve anymore.
             if eval_score >= beta {
@@ -139,6 +173,11 @@ impl Search {
             }
         }
 
+        // In check without a legal move, it's checkmate.
+        if in_check && legal_moves_found == 0 {
+            return -CHECKMATE + (refs.search_info.ply as i16);
+        }
+
         // We have traversed the entire move list and found the best score for us,
         // so we return this.
         alpha
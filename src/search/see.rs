@@ -0,0 +1,248 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// see.rs implements Static Exchange Evaluation. It determines the outcome
+// of a sequence of captures on a single square, where both sides always
+// capture with their least valuable piece, and may stop capturing as soon
+// as this would lose material.
+
+use super::Search;
+use crate::{
+    board::{
+        defs::{Pieces, Ranks, Sq, BB_SQUARES},
+        Board,
+    },
+    defs::{Bitboard, NrOf, Sides, Square, EMPTY},
+    evaluation::defs::{PieceValues, PIECE_VALUES_MG},
+    misc::bits,
+    movegen::{defs::Move, MoveGenerator},
+};
+
+// The longest possible exchange is 32 captures; one extra for the start.
+const MAX_EXCHANGE: usize = 33;
+
+// Pieces can be pinned to a king from eight directions, so there are at
+// most eight pins per side.
+const MAX_PINS: usize = 16;
+
+// The king has no material value in the evaluation. In an exchange, it
+// must never be captured, so it gets a value that outweighs everything.
+const SEE_VALUES: PieceValues = see_values();
+const fn see_values() -> PieceValues {
+    let mut values = PIECE_VALUES_MG;
+    values[Pieces::KING] = 20_000;
+    values
+}
+
+// Pieces in order of increasing value, to find the least valuable attacker.
+const LEAST_VALUABLE_FIRST: [usize; NrOf::PIECE_TYPES] = [
+    Pieces::PAWN,
+    Pieces::KNIGHT,
+    Pieces::BISHOP,
+    Pieces::ROOK,
+    Pieces::QUEEN,
+    Pieces::KING,
+];
+
+impl Search {
+    // Returns the material balance for the side to move, after all the
+    // captures on the to-square of the given capture move are done.
+    pub fn see(board: &Board, mg: &MoveGenerator, m: Move) -> i16 {
+        let to = m.to();
+        let mut gain = [0i16; MAX_EXCHANGE];
+        let mut depth = 0;
+        let mut side = board.us();
+        let mut occupancy = board.occupancy();
+
+        // The first capture. An en-passant capture takes a pawn that is
+        // not on the to-square; a promotion gains the promoted piece.
+        let mut on_square = m.piece();
+        gain[0] = SEE_VALUES[m.captured()];
+        if m.en_passant() {
+            gain[0] = SEE_VALUES[Pieces::PAWN];
+            occupancy ^= BB_SQUARES[Sq::new(to).en_passant_partner().index()];
+        }
+        if m.promoted() != Pieces::NONE {
+            gain[0] += SEE_VALUES[m.promoted()] - SEE_VALUES[Pieces::PAWN];
+            on_square = m.promoted();
+        }
+        let mut from_bb = BB_SQUARES[m.from()];
+
+        // A pawn that captures on the first or last rank promotes.
+        let to_rank = Sq::new(to).rank();
+        let promotes = to_rank == Ranks::R1 || to_rank == Ranks::R8;
+
+        // Pinned pieces can't join the exchange, as long as the piece
+        // pinning them is still there. (If it is on the to-square, the
+        // first capture has already taken it.) Each pinned piece is kept
+        // with its own pinner, so taking one pinner only frees the piece
+        // it pinned.
+        let mut pins = [(EMPTY, EMPTY); MAX_PINS];
+        let mut nr_of_pins = 0;
+        for side in [Sides::WHITE, Sides::BLACK] {
+            let king_square = board.king_square(side);
+            board.for_each_xray(mg, king_square, side, side ^ 1, |pinner, pinned| {
+                if pinned > 0 {
+                    pins[nr_of_pins] = (pinned, BB_SQUARES[pinner]);
+                    nr_of_pins += 1;
+                }
+            });
+        }
+
+        // Keep capturing with the least valuable attacker. gain[depth] is
+        // the material the side capturing at that depth would have if the
+        // piece on the square were taken back.
+        loop {
+            depth += 1;
+            side ^= 1;
+
+            // Remove the piece that captured last, revealing any sliders
+            // behind it, and find the next attacker.
+            occupancy ^= from_bb;
+            let attackers = Search::attackers_to(board, mg, to, occupancy) & occupancy;
+            let mut allowed = attackers;
+            for &(pinned, pinner) in &pins[..nr_of_pins] {
+                if pinner & occupancy & !BB_SQUARES[to] > 0 {
+                    allowed &= !pinned;
+                }
+            }
+            let (piece, square) = match Search::least_valuable_attacker(board, allowed, side) {
+                Some(attacker) => attacker,
+                None => break,
+            };
+
+            // The king can't capture into a square that is still attacked.
+            // A pinned piece still guards the square, so all the
+            // attackers count here.
+            if piece == Pieces::KING
+                && Search::least_valuable_attacker(board, attackers, side ^ 1).is_some()
+            {
+                break;
+            }
+
+            gain[depth] = SEE_VALUES[on_square] - gain[depth - 1];
+            on_square = piece;
+            from_bb = BB_SQUARES[square];
+            if piece == Pieces::PAWN && promotes {
+                gain[depth] += SEE_VALUES[Pieces::QUEEN] - SEE_VALUES[Pieces::PAWN];
+                on_square = Pieces::QUEEN;
+            }
+
+            if depth + 1 >= MAX_EXCHANGE {
+                break;
+            }
+        }
+
+        // Go back through the exchange. At each step, the side to capture
+        // either captures or stands pat, whichever is better for it.
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+
+        gain[0]
+    }
+
+    // Returns the pieces of both sides that attack the given square, with
+    // the given occupancy. Sliders behind a removed piece are included.
+    fn attackers_to(
+        board: &Board,
+        mg: &MoveGenerator,
+        square: Square,
+        occupancy: Bitboard,
+    ) -> Bitboard {
+        let white = &board.bb_pieces[Sides::WHITE];
+        let black = &board.bb_pieces[Sides::BLACK];
+        let rooks = white[Pieces::ROOK] | black[Pieces::ROOK];
+        let bishops = white[Pieces::BISHOP] | black[Pieces::BISHOP];
+        let queens = white[Pieces::QUEEN] | black[Pieces::QUEEN];
+        let knights = white[Pieces::KNIGHT] | black[Pieces::KNIGHT];
+        let kings = white[Pieces::KING] | black[Pieces::KING];
+
+        (mg.get_pawn_attacks(Sides::BLACK, square) & white[Pieces::PAWN])
+            | (mg.get_pawn_attacks(Sides::WHITE, square) & black[Pieces::PAWN])
+            | (mg.get_non_slider_attacks(Pieces::KNIGHT, square) & knights)
+            | (mg.get_non_slider_attacks(Pieces::KING, square) & kings)
+            | (mg.get_slider_attacks(Pieces::ROOK, square, occupancy) & (rooks | queens))
+            | (mg.get_slider_attacks(Pieces::BISHOP, square, occupancy) & (bishops | queens))
+    }
+
+    // Returns the least valuable piece of the given side among the
+    // attackers, and the square it is on.
+    fn least_valuable_attacker(
+        board: &Board,
+        attackers: Bitboard,
+        side: usize,
+    ) -> Option<(usize, Square)> {
+        for piece in LEAST_VALUABLE_FIRST {
+            let mut bb = attackers & board.bb_pieces[side][piece];
+            if bb > 0 {
+                return Some((piece, bits::next(&mut bb)));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Exchanges that are hard to get right for static exchange evaluation.
+    // Each test has an FEN-string, a capture, and the expected SEE value for
+    // the side to move, in centipawns (Q = 900, R = 500, B = 330, N = 320,
+    // P = 100).
+    const SEE_TESTS: [(&str, &str, i16); 6] = [
+        // The pawn on d5 is defended by a pawn: N for P.
+        ("4k3/8/2p5/3p4/8/4N3/8/4K3 w - - 0 1", "e3d5", -220),
+        // The rook on d1 x-rays through the rook on d2: the pawn is won.
+        ("3r2k1/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", 100),
+        // The knight on d7 is pinned to its king, so it can't take back.
+        ("4k3/3n4/8/1B2p3/8/8/8/4R1K1 w - - 0 1", "e1e5", 100),
+        // The bishop on e3 is pinned by the bishop on c5. Taking the pinner
+        // frees it to take back on c5.
+        ("7k/3n4/8/2b5/1P6/4B3/8/6K1 w - - 0 1", "b4c5", 330),
+        // The same, with the knight on g4 pinned by the rook on g8. That pin
+        // stays, but the bishop on e3 is still freed.
+        ("6rk/3n4/8/2b5/1P4N1/4B3/8/6K1 w - - 0 1", "b4c5", 330),
+        // The pawn takes back on d1 and promotes to a queen.
+        ("k7/8/8/8/8/7K/4p3/2Rn4 w - - 0 1", "c1d1", -980),
+    ];
+
+    #[test]
+    fn see_values_of_hard_exchanges() {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+
+        for (fen, uci, expected) in SEE_TESTS {
+            assert!(board.fen_read(Some(fen)).is_ok(), "{fen}");
+            let legal_moves = board.legal_moves(&mg);
+            let m = (0..legal_moves.len())
+                .map(|i| legal_moves.get_move(i))
+                .find(|m| m.as_string() == uci)
+                .unwrap_or_else(|| panic!("{uci} is not legal in {fen}"));
+            assert_eq!(Search::see(&board, &mg, m), expected, "{fen} {uci}");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// skill.rs implements playing at a limited strength (the UCI "Skill Level"
+// option). At lower levels, the search depth is reduced, and the engine
+// may pick a move that is somewhat worse than the best move it found.
+
+use super::{
+    defs::{RootMove, MAX_SKILL_LEVEL},
+    Search,
+};
+use crate::movegen::defs::Move;
+use rand::Rng;
+
+// Each level below the maximum widens the margin (in centipawns) within
+// which a move may score worse than the best move and still be picked.
+const MARGIN_PER_LEVEL: i32 = 15;
+
+impl Search {
+    // Maximum search depth at the given skill level. Level 0 searches
+    // only 1 ply deep, and each further level adds half a ply.
+    pub fn skill_depth(skill_level: u8) -> i8 {
+        1 + (skill_level / 2) as i8
+    }
+
+    // Picks a move from the scored root moves. At the maximum skill level
+    // this is always the best move. Below that, each move scoring within
+    // the margin of the best move is a candidate. One of the candidates
+    // is picked at random; the closer a move's score is to the best
+    // score, the more likely it is to be picked. The random number
+    // generator is passed in, so a seeded one can be used to get
+    // reproducible results.
+    pub fn pick_skill_move(
+        root_moves: &[RootMove],
+        skill_level: u8,
+        rng: &mut impl Rng,
+    ) -> Option<Move> {
+        let best = root_moves.iter().max_by_key(|rm| rm.score)?;
+
+        if skill_level >= MAX_SKILL_LEVEL {
+            return Some(best.m);
+        }
+
+        // Calculate the weight of each move. Moves outside of the margin
+        // get a weight of 0, so they will never be picked.
+        let margin = (MAX_SKILL_LEVEL - skill_level) as i32 * MARGIN_PER_LEVEL;
+        let weights: Vec<i32> = root_moves
+            .iter()
+            .map(|rm| {
+                let difference = best.score as i32 - rm.score as i32;
+                (margin - difference + 1).max(0)
+            })
+            .collect();
+
+        // Pick a random number within the total weight, and find the
+        // move it falls on.
+        let total: i32 = weights.iter().sum();
+        let mut pick = rng.gen_range(0..total);
+        for (rm, weight) in root_moves.iter().zip(weights.iter()) {
+            if pick < *weight {
+                return Some(rm.m);
+            }
+            pick -= weight;
+        }
+
+        Some(best.m)
+    }
+}
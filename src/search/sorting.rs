@@ -24,60 +24,143 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 // Move sorting routines.
 
 use super::{
-    defs::{SearchRefs, MAX_KILLER_MOVES},
+    defs::{SearchRefs, MAX_HISTORY, MAX_KILLER_MOVES},
     Search,
 };
-use crate::{board::defs::Pieces, defs::NrOf, movegen::defs::MoveList, movegen::defs::ShortMove};
+use crate::{
+    board::defs::Pieces, defs::NrOf, evaluation::defs::PIECE_VALUES_MG, movegen::defs::MoveList,
+    movegen::defs::ShortMove,
+};
 
-const MVV_LVA_OFFSET: u32 = u32::MAX - 256;
-const TTMOVE_SORT_VALUE: u32 = 60;
+// Captures, queen promotions and the TT-move are sorted above
+// MVV_LVA_OFFSET, killers below it. The offset leaves enough room for the
+// largest MVV-LVA value plus the queen promotion bonus.
+const MVV_LVA_OFFSET: u32 = u32::MAX - 32_768;
+const TTMOVE_SORT_VALUE: u32 = 20_000;
 const KILLER_VALUE: u32 = 10;
 
+// Promoting to a queen gains the difference between a queen and a pawn.
+// Underpromotions don't get this bonus: they are almost never the best
+// move, so they are sorted in with the other quiet moves (or with the
+// other captures, if they capture something). They are still generated
+// and searched, because sometimes they do win.
+const QUEEN_PROMOTION_VALUE: u32 =
+    ((PIECE_VALUES_MG[Pieces::QUEEN] - PIECE_VALUES_MG[Pieces::PAWN]) * 10) as u32;
+
+type MvvLvaTable = [[u16; NrOf::PIECE_TYPES + 1]; NrOf::PIECE_TYPES + 1];
+
 // MVV_VLA[victim][attacker]
-pub const MVV_LVA: [[u16; NrOf::PIECE_TYPES + 1]; NrOf::PIECE_TYPES + 1] = [
-    [0, 0, 0, 0, 0, 0, 0],       // victim K, attacker K, Q, R, B, N, P, None
-    [50, 51, 52, 53, 54, 55, 0], // victim Q, attacker K, Q, R, B, N, P, None
-    [40, 41, 42, 43, 44, 45, 0], // victim R, attacker K, Q, R, B, N, P, None
-    [30, 31, 32, 33, 34, 35, 0], // victim B, attacker K, Q, R, B, N, P, None
-    [20, 21, 22, 23, 24, 25, 0], // victim K, attacker K, Q, R, B, N, P, None
-    [10, 11, 12, 13, 14, 15, 0], // victim P, attacker K, Q, R, B, N, P, None
-    [0, 0, 0, 0, 0, 0, 0],       // victim None, attacker K, Q, R, B, N, P, None
-];
+pub const MVV_LVA: MvvLvaTable = create_mvv_lva();
+
+// The MVV-LVA table is derived from the middlegame piece values, so move
+// ordering agrees with the evaluation on what each piece is worth. The
+// victim's value dominates; the attacker's value only breaks ties
+// between captures of the same victim. Captures of or by "None" are 0.
+const fn create_mvv_lva() -> MvvLvaTable {
+    let mut table: MvvLvaTable = [[0; NrOf::PIECE_TYPES + 1]; NrOf::PIECE_TYPES + 1];
+    let mut victim = 0;
+
+    while victim < Pieces::NONE {
+        let mut attacker = 0;
+        while attacker < Pieces::NONE {
+            let v = PIECE_VALUES_MG[victim] as u16;
+            let a = PIECE_VALUES_MG[attacker] as u16;
+            if v > 0 {
+                table[victim][attacker] = v * 10 - a / 10;
+            }
+            attacker += 1;
+        }
+        victim += 1;
+    }
+
+    table
+}
 
 impl Search {
     pub fn score_moves(ml: &mut MoveList, tt_move: ShortMove, refs: &SearchRefs) {
+        // At the root, a TT move that has been dropping in score for a
+        // few iterations in a row doesn't get sorted first.
+        let is_root = refs.search_info.ply == 0;
+        let unstable = refs.search_info.unstable_root_move;
+        let tt_move = if is_root && tt_move.get_move() == unstable.get_move() {
+            ShortMove::new(0)
+        } else {
+            tt_move
+        };
+
         for i in 0..ml.len() {
             let m = ml.get_mut_move(i);
             let mut value: u32 = 0;
 
-            // Sort order priority is: TT Move first, then captures, then
-            // quiet moves that are in the list of killer moves.
+            // Sort order priority is: TT Move first, then captures and
+            // queen promotions, then quiet moves that are in the list of
+            // killer moves.
+            let is_queen_promotion = m.promoted() == Pieces::QUEEN;
             if m.get_move() == tt_move.get_move() {
                 value = MVV_LVA_OFFSET + TTMOVE_SORT_VALUE;
-            } else if m.captured() != Pieces::NONE {
-                // Order captures higher than MVV_LVA_OFFSET
+            } else if m.captured() != Pieces::NONE || is_queen_promotion {
+                // Order captures and queen promotions higher than
+                // MVV_LVA_OFFSET. A capture that also promotes to a queen
+                // gets both values.
                 value = MVV_LVA_OFFSET + MVV_LVA[m.captured()][m.piece()] as u32;
+                if is_queen_promotion {
+                    value += QUEEN_PROMOTION_VALUE;
+                }
             } else {
                 let ply = refs.search_info.ply as usize;
                 let mut n = 0;
                 while n < MAX_KILLER_MOVES && value == 0 {
                     let killer = refs.search_info.killer_moves[ply][n];
                     if m.get_move() == killer.get_move() {
-                        // Order killers below MVV_LVA_OFFSET
-                        value = MVV_LVA_OFFSET - ((i as u32 + 1) * KILLER_VALUE);
+                        // Order killers below MVV_LVA_OFFSET, the first
+                        // killer slot above the second.
+                        value = MVV_LVA_OFFSET - ((n as u32 + 1) * KILLER_VALUE);
                     }
                     n += 1;
                 }
             }
 
-            /*
-                // If still not sorted, try to sort by history heuristic.
-                if value == 0 {
-                    let piece = m.piece();
-                    let to = m.to();
-                    value = refs.search_info.history_heuristic[refs.board.us()][piece][to];
+            // If still not sorted, try to sort by history heuristic. The
+            // history score is shifted up by MAX_HISTORY to make it
+            // positive; this keeps it far below the killer moves.
+            if value == 0 {
+                let history = Search::history_score(*m, refs);
+                value = (history + MAX_HISTORY) as u32;
+            }
+
+            m.set_sort_score(value);
+        }
+    }
+
+    // Scores the captures for quiescence search. Captures that don't lose
+    // material according to SEE are searched first, in MVV-LVA order, so
+    // the search can cut off quickly. Losing captures are searched last,
+    // the ones that lose the least material first.
+    pub fn score_captures(ml: &mut MoveList, refs: &SearchRefs) {
+        for i in 0..ml.len() {
+            let m = ml.get_mut_move(i);
+            let mut value: u32;
+
+            // Capturing a piece that is worth at least as much as the
+            // capturing piece can never lose material, so SEE is only
+            // needed for the other captures.
+            let victim = PIECE_VALUES_MG[m.captured()];
+            let attacker = PIECE_VALUES_MG[m.piece()];
+            let see = if victim >= attacker {
+                0
+            } else {
+                Search::see(refs.board, refs.mg, *m)
+            };
+
+            if see >= 0 {
+                value = MVV_LVA_OFFSET + MVV_LVA[m.captured()][m.piece()] as u32;
+                if m.promoted() == Pieces::QUEEN {
+                    value += QUEEN_PROMOTION_VALUE;
                 }
-            */
+            } else {
+                // Shift the (negative) SEE value up to make it positive.
+                value = (see as i32 - i16::MIN as i32) as u32;
+            }
 
             m.set_sort_score(value);
         }
@@ -0,0 +1,859 @@
+/* =======================================================================
+Rustic is a chess playing engine.
+Copyright (C) 2019-2024, Marcel Vanthoor
+https://rustic-chess.org/
+
+Rustic is written in the Rust programming language. It is an original
+work, not derived from any engine that came before it. However, it does
+use a lot of concepts which are well-known and are in use by most if not
+all classical alpha/beta-based chess engines.
+
+Rustic is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License version 3 as published by
+the Free Software Foundation.
+
+Rustic is distributed in the hope that it will be useful, but WITHOUT
+ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+FITNESS FOR A PARTICULAR PURPOSE.  See the GNU General Public License
+for more details.
+
+You should have received a copy of the GNU General Public License along
+with this program.  If not, see <http://www.gnu.org/licenses/>.
+======================================================================= */
+
+// tests.rs runs searches in the test thread, without the search thread
+// and the engine around them. The tests can then look at the result, the
+// reports and the tables the search leaves behind.
+
+use super::{
+    defs::{
+        GameTime, LmrTable, SearchControl, SearchInfo, SearchMode, SearchParams, SearchRefs,
+        SearchReport, SearchResult, SearchSummary, CHECKMATE, CHECKMATE_THRESHOLD,
+        EXTENSION_BUDGET, INF, QS_MAX_DEPTH, SINGLE_REPLY_DEPTH, STALEMATE,
+    },
+    Search,
+};
+use crate::{
+    board::Board,
+    engine::defs::{AnalysisData, HashFlag, Information, SearchData, TT},
+    evaluation::{self, pawns::PawnHash},
+    movegen::{
+        defs::{Move, ShortMove},
+        MoveGenerator,
+    },
+};
+use crossbeam_channel::{Receiver, Sender};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+const TT_SIZE: usize = 4; // MB
+
+// Everything a search needs, owned by the test. The tests of other
+// search modules use it as well.
+pub(super) struct TestSearch {
+    board: Board,
+    mg: Arc<MoveGenerator>,
+    tt: Arc<Mutex<TT<SearchData>>>,
+    pawn_hash: PawnHash,
+    analysis: Option<TT<AnalysisData>>,
+    lmr_table: LmrTable,
+    pub(super) params: SearchParams,
+    info: SearchInfo,
+    _control_tx: Sender<SearchControl>,
+    control_rx: Receiver<SearchControl>,
+    report_tx: Sender<Information>,
+    report_rx: Receiver<Information>,
+}
+
+impl TestSearch {
+    // Sets up a search of the given position, to the given depth.
+    pub(super) fn new(fen: &str, depth: i8) -> Self {
+        let mut board = Board::new();
+        assert!(board.fen_read(Some(fen)).is_ok());
+        let (control_tx, control_rx) = crossbeam_channel::unbounded();
+        let (report_tx, report_rx) = crossbeam_channel::unbounded();
+
+        let mut params = SearchParams::new();
+        params.search_mode = SearchMode::Depth;
+        params.depth = depth;
+        params.quiet = true;
+
+        Self {
+            board,
+            mg: Arc::new(MoveGenerator::new()),
+            tt: Arc::new(Mutex::new(TT::new(TT_SIZE))),
+            pawn_hash: PawnHash::new(),
+            analysis: None,
+            lmr_table: Search::lmr_table(),
+            params,
+            info: SearchInfo::new(),
+            _control_tx: control_tx,
+            control_rx,
+            report_tx,
+            report_rx,
+        }
+    }
+
+    // Runs the function with the references a search works with.
+    pub(super) fn with_refs<T>(&mut self, f: impl FnOnce(&mut SearchRefs) -> T) -> T {
+        let mut refs = SearchRefs {
+            board: &mut self.board,
+            mg: &self.mg,
+            tt: &self.tt,
+            tt_enabled: true,
+            pawn_hash: &mut self.pawn_hash,
+            analysis: self.analysis.as_mut(),
+            lmr_table: &self.lmr_table,
+            search_params: &mut self.params,
+            search_info: &mut self.info,
+            control_rx: &self.control_rx,
+            report_tx: &self.report_tx,
+        };
+
+        f(&mut refs)
+    }
+
+    // Runs the search, as the search thread would.
+    pub(super) fn run(&mut self) -> SearchResult {
+        self.info = SearchInfo::new();
+        self.with_refs(Search::iterative_deepening)
+    }
+
+    // Searches one node with the given window, as the root of the search.
+    fn alpha_beta(&mut self, depth: i8, alpha: i16, beta: i16) -> i16 {
+        self.info = SearchInfo::new();
+        self.with_refs(|refs| {
+            refs.search_info.timer_start();
+            Search::alpha_beta(depth, alpha, beta, &mut Vec::new(), refs)
+        })
+    }
+
+    // Searches one node with the given window, below the root. Root-only
+    // rules, such as not reducing late moves, then don't apply. Returns
+    // the score and the PV.
+    fn alpha_beta_below_root(&mut self, depth: i8, alpha: i16, beta: i16) -> (i16, Vec<Move>) {
+        self.info = SearchInfo::new();
+        self.info.ply = 1;
+        let mut pv = Vec::new();
+        let score = self.with_refs(|refs| {
+            refs.search_info.timer_start();
+            Search::alpha_beta(depth, alpha, beta, &mut pv, refs)
+        });
+        (score, pv)
+    }
+
+    // Returns the search summaries that were sent since the last call.
+    pub(super) fn summaries(&self) -> Vec<SearchSummary> {
+        self.report_rx
+            .try_iter()
+            .filter_map(|report| match report {
+                Information::Search(SearchReport::SearchSummary(s)) => Some(s),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Returns the TT entry of the current position.
+    fn tt_entry(&self) -> Option<SearchData> {
+        let key = self.board.game_state.zobrist_key;
+        self.tt.lock().unwrap().probe(key).copied()
+    }
+
+    fn clear_tt(&mut self) {
+        self.tt.lock().unwrap().clear();
+    }
+}
+
+// A quiet middlegame position, with no tactics at low depth.
+pub(super) const QUIET: &str = "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 1";
+
+#[test]
+fn tt_flag_follows_the_window() {
+    const DEPTH: i8 = 3;
+    let mut search = TestSearch::new(QUIET, DEPTH);
+
+    // The score is within the window.
+    let score = search.alpha_beta(DEPTH, -INF, INF);
+    let entry = search.tt_entry().unwrap();
+    assert!(matches!(entry.flag(), HashFlag::Exact));
+    assert_eq!(entry.get(DEPTH, 0, -INF, INF).0, Some(score));
+
+    // The score is above the window: the search fails high.
+    search.clear_tt();
+    let (alpha, beta) = (score - 200, score - 100);
+    assert!(search.alpha_beta(DEPTH, alpha, beta) >= beta);
+    let entry = search.tt_entry().unwrap();
+    assert!(matches!(entry.flag(), HashFlag::Beta));
+    assert_eq!(entry.get(DEPTH, 0, alpha, beta).0, Some(beta));
+
+    // The score is below the window: the search fails low.
+    search.clear_tt();
+    let (alpha, beta) = (score + 100, score + 200);
+    assert!(search.alpha_beta(DEPTH, alpha, beta) <= alpha);
+    let entry = search.tt_entry().unwrap();
+    assert!(matches!(entry.flag(), HashFlag::Alpha));
+    assert_eq!(entry.get(DEPTH, 0, alpha, beta).0, Some(alpha));
+}
+
+#[test]
+fn quiet_root_move_is_not_pruned() {
+    // WAC.001: the quiet Qg6 mates in two. It is a late, quiet move with
+    // no history yet, which the pruning and reductions at interior nodes
+    // would treat as unpromising.
+    let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1";
+    let mut search = TestSearch::new(fen, 4);
+    let result = search.run();
+    assert_eq!(result.best_move.as_string(), "g3g6");
+    assert_eq!(Search::moves_to_mate(result.score), 2);
+}
+
+#[test]
+fn no_tablebase_hits_without_tablebases() {
+    let mut search = TestSearch::new(QUIET, 4);
+    let result = search.run();
+    assert_eq!(result.depth, 4);
+    assert!(search.board.clone().make(result.best_move, &search.mg));
+
+    let summaries = search.summaries();
+    assert_eq!(summaries.last().map(|s| s.depth), Some(4));
+    assert!(summaries.iter().all(|s| s.tb_hits == 0));
+    assert_eq!(search.info.tb_hits, 0);
+}
+
+#[test]
+fn checkmated_root_has_no_move() {
+    let mut search = TestSearch::new("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1", 6);
+    let result = search.run();
+    assert_eq!(result.best_move.get_move(), 0);
+    assert_eq!(result.score, -CHECKMATE);
+    assert_eq!(result.nodes, 0);
+}
+
+#[test]
+fn stalemated_root_has_no_move() {
+    let mut search = TestSearch::new("k7/8/1QK5/8/8/8/8/8 b - - 0 1", 6);
+    let result = search.run();
+    assert_eq!(result.best_move.get_move(), 0);
+    assert_eq!(result.score, STALEMATE);
+    assert_eq!(result.nodes, 0);
+}
+
+#[test]
+fn single_reply_is_searched_shallow_in_a_game() {
+    // The only legal move is Kxh2.
+    let mut search = TestSearch::new("6k1/8/8/8/8/8/7q/7K w - - 0 1", 20);
+    search.params.search_mode = SearchMode::GameTime;
+    search.params.game_time = GameTime::new(60_000, 60_000, 0, 0, None);
+    let result = search.run();
+    assert!(result.depth <= SINGLE_REPLY_DEPTH);
+    assert_eq!(result.best_move.as_string(), "h1h2");
+}
+
+#[test]
+fn single_reply_waits_out_the_min_think_time() {
+    const MIN_THINK_TIME: u128 = 50;
+    let mut search = TestSearch::new("6k1/8/8/8/8/8/7q/7K w - - 0 1", 20);
+    search.params.search_mode = SearchMode::GameTime;
+    search.params.game_time = GameTime::new(60_000, 60_000, 0, 0, None);
+    search.params.min_think_time = MIN_THINK_TIME;
+
+    let start = Instant::now();
+    let result = search.run();
+    assert!(start.elapsed().as_millis() >= MIN_THINK_TIME);
+    assert_eq!(result.best_move.as_string(), "h1h2");
+}
+
+#[test]
+fn single_reply_is_searched_fully_to_a_fixed_depth() {
+    let mut search = TestSearch::new("6k1/8/8/8/8/8/7q/7K w - - 0 1", 6);
+    let result = search.run();
+    assert_eq!(result.depth, 6);
+    assert_eq!(result.best_move.as_string(), "h1h2");
+}
+
+#[test]
+fn static_eval_is_taken_from_the_tt() {
+    let mut search = TestSearch::new(QUIET, 2);
+
+    // A new position is evaluated, and the evaluation is stored.
+    let eval = evaluation::evaluate_position(&search.board, &mut search.pawn_hash);
+    search.alpha_beta(2, -INF, INF);
+    assert_eq!(search.tt_entry().unwrap().static_eval(), Some(eval));
+
+    // A position searched before is not evaluated again. A made-up
+    // evaluation in the TT shows where the evaluation came from.
+    let fake = eval + 1000;
+    let data = SearchData::create(1, 0, HashFlag::Alpha, 0, Some(fake), ShortMove::new(0));
+    let key = search.board.game_state.zobrist_key;
+    search.clear_tt();
+    search.tt.lock().unwrap().insert(key, data);
+    search.alpha_beta(2, -INF, INF);
+    assert_eq!(search.info.static_evals[0], Some(fake));
+    assert_eq!(search.tt_entry().unwrap().static_eval(), Some(fake));
+}
+
+// KR vs. K: a win, unless the 50-move rule comes first.
+const KRK: &str = "8/8/8/4k3/8/8/8/R3K3 w - - 0 1";
+const KRK_CLOCK_96: &str = "8/8/8/4k3/8/8/8/R3K3 w - - 96 1";
+
+#[test]
+fn tt_scores_ignore_the_halfmove_clock_near_the_move_rule() {
+    let cold = TestSearch::new(KRK_CLOCK_96, 8).run().score;
+    assert_eq!(cold, 0);
+
+    // The TT is filled at a low clock: no cutoff at the high clock.
+    let mut low = TestSearch::new(KRK, 8);
+    assert!(low.run().score > 0);
+    let mut high = TestSearch::new(KRK_CLOCK_96, 8);
+    high.tt = Arc::clone(&low.tt);
+    assert_eq!(high.run().score, cold);
+}
+
+#[test]
+fn no_tt_stores_near_the_move_rule() {
+    let mut high = TestSearch::new(KRK_CLOCK_96, 8);
+    high.run();
+    assert!(high.tt_entry().is_none());
+
+    // The drawn scores of the high clock are not used at a low clock.
+    let cold = TestSearch::new(KRK, 8).run().score;
+    let mut low = TestSearch::new(KRK, 8);
+    low.tt = Arc::clone(&high.tt);
+    assert_eq!(low.run().score, cold);
+}
+
+#[test]
+fn reduced_best_move_gets_an_exact_score() {
+    // WAC.001 again. Below the root, the quiet Qg6 comes late and is
+    // searched with a reduced depth first. It must go through the whole
+    // re-search chain to get its exact mate score: at ply 1, a mate in
+    // two is found at ply 4.
+    let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1";
+    let mut search = TestSearch::new(fen, 6);
+    let (score, pv) = search.alpha_beta_below_root(6, -INF, INF);
+    assert_eq!(score, CHECKMATE - 4);
+    assert_eq!(
+        pv.first().map(|m| m.as_string()),
+        Some(String::from("g3g6"))
+    );
+}
+
+#[test]
+fn analysis_annotates_the_root() {
+    let mut search = TestSearch::new(QUIET, 4);
+    search.params.analyse_mode = true;
+    search.analysis = Some(TT::new(1));
+    let result = search.run();
+
+    let key = search.board.game_state.zobrist_key;
+    let annotation = *search.analysis.as_ref().unwrap().probe(key).unwrap();
+    assert!(matches!(annotation.flag(), HashFlag::Exact));
+    assert_eq!(annotation.value(), result.score);
+    assert!(annotation.nodes() > 0 && annotation.nodes() <= result.nodes);
+    assert!(annotation.static_eval().is_some());
+
+    let legal = search.board.legal_moves(&search.mg);
+    let best_move = annotation.best_move().get_move();
+    assert!((0..legal.len()).any(|i| legal.get_move(i).get_move() == best_move));
+    assert_eq!(best_move, result.best_move.get_move());
+}
+
+#[test]
+fn analyse_mode_turns_off_pruning() {
+    const DEPTH: i8 = 8;
+    let mut pruned = TestSearch::new(QUIET, DEPTH);
+    let game = pruned.run();
+
+    let mut full = TestSearch::new(QUIET, DEPTH);
+    full.params.analyse_mode = true;
+    let analysis = full.run();
+
+    assert!(analysis.nodes > game.nodes);
+    assert_eq!(analysis.best_move.as_string(), game.best_move.as_string());
+
+    // White can't promote the a-pawn, or drive the king out of the corner.
+    let mut drawn = TestSearch::new("k7/P7/2K5/8/8/8/8/8 w - - 0 1", DEPTH);
+    drawn.params.analyse_mode = true;
+    assert_eq!(drawn.run().score, 0);
+}
+
+// Searches QUIET, and then plays the expected line of the result, as the
+// game would continue. Returns the search, with its TT still filled.
+fn after_expected_line() -> TestSearch {
+    let mut search = TestSearch::new(QUIET, 6);
+    let result = search.run();
+    assert!(result.pv.len() >= 2);
+    for m in &result.pv[..2] {
+        assert!(search.board.make(*m, &search.mg));
+    }
+    search
+}
+
+#[test]
+fn tt_is_kept_for_the_next_search() {
+    let mut kept = after_expected_line();
+    assert!(kept.tt_entry().is_some());
+    let with_tt = kept.run();
+
+    let mut cleared = after_expected_line();
+    cleared.clear_tt();
+    let without_tt = cleared.run();
+
+    assert_eq!(with_tt.depth, without_tt.depth);
+    assert!(with_tt.nodes < without_tt.nodes);
+}
+
+// Searches the position to depth 10, with the given number of score
+// drops before the root TT move loses priority. Returns the result and
+// the best move of each iteration.
+fn root_drops_search(fen: &str, drops: u8) -> (SearchResult, Vec<String>) {
+    let mut search = TestSearch::new(fen, 10);
+    search.params.unstable_root_drops = drops;
+    let result = search.run();
+    let best_moves = search
+        .summaries()
+        .iter()
+        .filter_map(|s| s.pv.first().map(|m| m.as_string()))
+        .collect();
+    (result, best_moves)
+}
+
+#[test]
+fn unstable_root_move_is_recovered() {
+    // The first two iterations play e7e5. Once its score drops, it loses
+    // priority and d8c8 is the best move at depths 3 and 4. The search
+    // then settles on f7f5, like the search that doesn't track the drops.
+    let fen = "3k4/1pp1pp2/8/8/8/8/1PP1PP2/3K4 b - - 0 1";
+    let (off, off_moves) = root_drops_search(fen, 0);
+    let (on, on_moves) = root_drops_search(fen, 1);
+
+    assert_eq!(on_moves.first(), Some(&String::from("e7e5")));
+    assert_ne!(on_moves, off_moves);
+    assert_eq!(on.best_move.as_string(), "f7f5");
+    assert_eq!(off.best_move.as_string(), "f7f5");
+    assert!(on.nodes <= off.nodes);
+}
+
+#[test]
+fn mate_at_the_horizon_is_found_without_extensions() {
+    // Ra8 mates. Searched to depth 1 without check extensions, the mated
+    // position is at the horizon, in check. Quiescence search finds that
+    // it has no evasions.
+    let mut search = TestSearch::new("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 1);
+    search.params.extension_budget = 0;
+    let result = search.run();
+    assert_eq!(result.best_move.as_string(), "a1a8");
+    assert_eq!(result.score, CHECKMATE - 1);
+}
+
+#[test]
+fn fixed_depth_search_is_reproducible() {
+    // At a fixed depth, neither time nor a node count ends the search
+    // early, so two searches of the same position do the same work.
+    let first = TestSearch::new(QUIET, 8).run();
+    let second = TestSearch::new(QUIET, 8).run();
+    assert_eq!(first.depth, 8);
+    assert_eq!(first.nodes, second.nodes);
+    assert_eq!(first.best_move.get_move(), second.best_move.get_move());
+    assert_eq!(first.score, second.score);
+}
+
+#[test]
+fn mate_is_preferred_over_a_repetition() {
+    // WAC.001 again, after the queen and a rook went back and forth. Qh3
+    // would now repeat a position, which scores as a draw. Qg6 still
+    // mates in two, and must be chosen.
+    let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1";
+    let mut search = TestSearch::new(fen, 6);
+    for uci in ["g3h3", "d8e8", "h3g3", "e8d8"] {
+        assert!(search.board.make_uci(uci, &search.mg).is_ok(), "{uci}");
+    }
+
+    let mut board = search.board.clone();
+    assert!(board.make_uci("g3h3", &search.mg).is_ok());
+    assert!(Search::is_repetition(&board) > 0);
+
+    let result = search.run();
+    assert_eq!(result.best_move.as_string(), "g3g6");
+    assert_eq!(result.score, CHECKMATE - 3);
+}
+
+// Searches the root of QUIET to the given depth, with the root moves
+// scored in the order they are searched. Returns that order.
+fn root_move_order(search: &mut TestSearch, depth: i8) -> Vec<String> {
+    search.params.skill_level = 0;
+    search.alpha_beta(depth, -INF, INF);
+    search
+        .info
+        .root_moves
+        .iter()
+        .map(|rm| rm.m.as_string())
+        .collect()
+}
+
+#[test]
+fn shallow_tt_move_is_searched_first() {
+    // a2a3 is a quiet move that isn't searched first on its own.
+    let mut search = TestSearch::new(QUIET, 4);
+    assert_ne!(root_move_order(&mut search, 4)[0], "a2a3");
+
+    // A depth 1 entry can't give a result at depth 4, and neither can an
+    // entry without a bound. Their move is still searched first.
+    let m = search
+        .board
+        .clone()
+        .make_uci("a2a3", &search.mg)
+        .ok()
+        .unwrap();
+    let key = search.board.game_state.zobrist_key;
+    for flag in [HashFlag::Alpha, HashFlag::Nothing] {
+        let data = SearchData::create(1, 0, flag, 0, None, m.to_short_move());
+        search.clear_tt();
+        search.tt.lock().unwrap().insert(key, data);
+        assert_eq!(root_move_order(&mut search, 4)[0], "a2a3");
+    }
+}
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// Sets up a search of the start position after the given moves.
+fn after_moves(moves: &[&str], depth: i8) -> TestSearch {
+    let mut search = TestSearch::new(START, depth);
+    for uci in moves {
+        assert!(search.board.make_uci(uci, &search.mg).is_ok(), "{uci}");
+    }
+    search
+}
+
+#[test]
+fn transposition_reuses_the_tt() {
+    // The same position, reached by two move orders.
+    let mut first = after_moves(&["e2e4", "e7e5", "g1f3", "b8c6"], 6);
+    let mut warm = after_moves(&["g1f3", "b8c6", "e2e4", "e7e5"], 6);
+    let mut cold = after_moves(&["g1f3", "b8c6", "e2e4", "e7e5"], 6);
+    let key = first.board.game_state.zobrist_key;
+    assert_eq!(warm.board.game_state.zobrist_key, key);
+
+    // The second move order finds the entries of the first search.
+    first.run();
+    warm.tt = Arc::clone(&first.tt);
+    assert!(warm.tt_entry().is_some());
+    let warm_result = warm.run();
+    let cold_result = cold.run();
+
+    assert_eq!(warm_result.depth, cold_result.depth);
+    assert!(warm_result.nodes * 2 < cold_result.nodes);
+}
+
+#[test]
+fn mate_search_stops_at_the_mate() {
+    // WAC.001: Qg6 mates in two, which is found within three plies.
+    let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1";
+    let mut search = TestSearch::new(fen, 0);
+    search.params.search_mode = SearchMode::Mate;
+    search.params.mate = 2;
+    let result = search.run();
+
+    assert_eq!(result.best_move.as_string(), "g3g6");
+    assert_eq!(Search::moves_to_mate(result.score), 2);
+    assert!(result.depth <= 3);
+    let summary = search.summaries().pop().unwrap();
+    assert_eq!(summary.mate, 2);
+}
+
+// Searches the current position in quiescence, starting at the given
+// quiescence depth. Returns the score and the selective depth.
+fn quiescence(search: &mut TestSearch, qs_depth: i8) -> (i16, i8) {
+    search.info = SearchInfo::new();
+    let score = search.with_refs(|refs| {
+        refs.search_info.timer_start();
+        Search::quiescence(qs_depth, -INF, INF, &mut Vec::new(), refs)
+    });
+    (score, search.info.seldepth)
+}
+
+#[test]
+fn quiescence_ends_on_a_long_capture_chain() {
+    // Queens and rooks face each other on three open files, so captures
+    // and recaptures can go on for many plies.
+    let mut search = TestSearch::new("1r1r1r1k/1q1q1q2/8/8/8/8/1Q1Q1Q2/1R1R1R1K w - - 0 1", 1);
+    let (score, seldepth) = quiescence(&mut search, 0);
+
+    assert!(score.abs() < CHECKMATE_THRESHOLD, "{score}");
+    assert!(seldepth > 8 && seldepth <= QS_MAX_DEPTH, "{seldepth}");
+
+    // At the maximum depth, quiescence only evaluates the position.
+    let eval = evaluation::evaluate_position(&search.board, &mut search.pawn_hash);
+    assert_eq!(quiescence(&mut search, QS_MAX_DEPTH), (eval, 0));
+    assert_eq!(search.info.nodes, 1);
+}
+
+#[test]
+fn see_ordering_saves_quiescence_nodes() {
+    // Kiwipete has many captures, and a lot of them lose material.
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let mut see = TestSearch::new(kiwipete, 1);
+    let (see_score, _) = quiescence(&mut see, 0);
+
+    let mut mvv_lva = TestSearch::new(kiwipete, 1);
+    mvv_lva.params.see_ordering = false;
+    let (mvv_lva_score, _) = quiescence(&mut mvv_lva, 0);
+
+    assert_eq!(see_score, mvv_lva_score);
+    assert!(see.info.nodes < mvv_lva.info.nodes);
+}
+
+#[test]
+fn repetition_draw_is_not_taken_from_the_tt() {
+    // White is a queen up, but the kings have moved back and forth. After
+    // 2... Kf8 3. Kg1, the reply Kg8 repeats the position after 1. Kg1
+    // Kg8, so black can draw.
+    let fen = "7k/8/8/8/8/8/8/1Q5K w - - 0 1";
+    let mut first = TestSearch::new(fen, 4);
+    for uci in ["h1g1", "h8g8", "g1h1", "g8f8", "h1g1"] {
+        assert!(first.board.make_uci(uci, &first.mg).is_ok(), "{uci}");
+    }
+    let (score, pv) = first.alpha_beta_below_root(4, -INF, INF);
+    assert_eq!(score, 0);
+    assert_eq!(pv[0].as_string(), "f8g8");
+
+    // The same position, reached without that history. Black can't
+    // repeat anything, so the draw found above must not come out of the
+    // TT. A null window at the draw score shows if it does.
+    let same = "5k2/8/8/8/8/8/8/1Q4K1 b - - 2 2";
+    let mut warm = TestSearch::new(same, 4);
+    let mut cold = TestSearch::new(same, 4);
+    assert_eq!(
+        warm.board.game_state.zobrist_key,
+        first.board.game_state.zobrist_key
+    );
+    warm.tt = Arc::clone(&first.tt);
+    let (warm_score, _) = warm.alpha_beta_below_root(4, -1, 0);
+    let (cold_score, _) = cold.alpha_beta_below_root(4, -1, 0);
+
+    assert_eq!(cold_score, -1);
+    assert_eq!(warm_score, -1);
+}
+
+#[test]
+fn result_matches_the_last_report() {
+    // Run the search in its own thread, as the engine does, and collect
+    // everything it reports until it has finished.
+    let mut board = Board::new();
+    assert!(board.fen_read(Some(QUIET)).is_ok());
+    let (report_tx, report_rx) = crossbeam_channel::unbounded::<Information>();
+    let tt = Arc::new(Mutex::new(TT::new(TT_SIZE)));
+    let mut search = Search::new();
+    search.init(
+        report_tx,
+        Arc::new(Mutex::new(board)),
+        Arc::new(MoveGenerator::new()),
+        tt,
+        true,
+    );
+
+    let mut params = SearchParams::new();
+    params.search_mode = SearchMode::Depth;
+    params.depth = 6;
+    params.quiet = true;
+    search.send(SearchControl::Start(params));
+
+    let mut summaries = Vec::new();
+    let result = loop {
+        match report_rx.recv().unwrap() {
+            Information::Search(SearchReport::Finished(result)) => break result,
+            Information::Search(SearchReport::SearchSummary(s)) => summaries.push(s),
+            _ => (),
+        }
+    };
+    search.send(SearchControl::Quit);
+    search.wait_for_shutdown();
+
+    // The bestmove sent to the GUI is the result's best move. It must be
+    // the first move of the result's PV, and of the last info line.
+    let last = summaries.last().expect("a search summary");
+    assert_eq!(result.pv[0].get_move(), result.best_move.get_move());
+    assert_eq!(last.pv[0].get_move(), result.best_move.get_move());
+    assert_eq!(
+        result.ponder_move.map(|m| m.get_move()),
+        result.pv.get(1).map(|m| m.get_move())
+    );
+    assert_eq!((last.depth, last.cp), (result.depth, result.score));
+}
+
+#[test]
+fn no_static_eval_in_check() {
+    // Black is in check from the bishop on b5.
+    let fen = "rnbqkbnr/ppp1pppp/8/1B1p4/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 2";
+    let mut search = TestSearch::new(fen, 2);
+    search.alpha_beta(2, -INF, INF);
+    assert_eq!(search.info.static_evals[0], None);
+
+    let mut search = TestSearch::new(QUIET, 2);
+    search.alpha_beta(2, -INF, INF);
+    assert!(search.info.static_evals[0].is_some());
+}
+
+// Returns true if the move is legal in the position of the search.
+fn is_legal(search: &mut TestSearch, m: Move) -> bool {
+    let moves = search.board.legal_moves(&search.mg);
+    (0..moves.len()).any(|i| moves.get_move(i).get_move() == m.get_move())
+}
+
+#[test]
+fn corrupt_root_tt_entry_gives_a_legal_move() {
+    // An exact, deep TT entry for the root, with a move that can't be
+    // played there: the rook on a1 is behind its own pawn. In a debug
+    // build, the search asserts that its best move is legal, so this
+    // test also fails there if the TT move surfaces.
+    let other = TestSearch::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", 1);
+    let garbage = other
+        .board
+        .clone()
+        .make_uci("a1a8", &other.mg)
+        .ok()
+        .unwrap();
+    let corrupt = |search: &mut TestSearch| {
+        let key = search.board.game_state.zobrist_key;
+        let data = SearchData::create(20, 0, HashFlag::Exact, 500, None, garbage.to_short_move());
+        search.tt.lock().unwrap().insert(key, data);
+    };
+
+    let mut search = TestSearch::new(QUIET, 4);
+    corrupt(&mut search);
+    let result = search.run();
+    assert!(
+        is_legal(&mut search, result.best_move),
+        "{}",
+        result.best_move.as_string()
+    );
+    assert_eq!(result.pv[0].get_move(), result.best_move.get_move());
+
+    // Stopped before depth 1 is done, there is no best move at all. The
+    // first legal move is played instead.
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let mut search = TestSearch::new(kiwipete, 4);
+    search.params.search_mode = SearchMode::Nodes;
+    search.params.nodes = 1;
+    corrupt(&mut search);
+    let result = search.run();
+    assert_eq!(result.depth, 0);
+    assert!(
+        is_legal(&mut search, result.best_move),
+        "{}",
+        result.best_move.as_string()
+    );
+}
+
+// Searches the position with the given extension budget.
+fn budget_search(fen: &str, depth: i8, budget: i8) -> SearchResult {
+    let mut search = TestSearch::new(fen, depth);
+    search.params.extension_budget = budget;
+    search.run()
+}
+
+#[test]
+fn extension_budget_bounds_a_forcing_line() {
+    // A smothered mate in four, with a check on every white move:
+    // Nf7+ Kg8 Nh6+ Kh8 Qg8+ Rxg8 Nf7#.
+    let fen = "r6k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1";
+    let unlimited = budget_search(fen, 7, i8::MAX);
+    let default = budget_search(fen, 7, EXTENSION_BUDGET);
+    let none = budget_search(fen, 7, 0);
+
+    // Without extensions, the mate is still found at depth 7, with a
+    // fraction of the nodes. The default budget never searches more
+    // than an unlimited one.
+    for result in [&unlimited, &default, &none] {
+        assert_eq!(result.best_move.as_string(), "g5f7");
+        assert_eq!(result.score, CHECKMATE - 7);
+    }
+    assert!(default.nodes <= unlimited.nodes);
+    assert!(
+        none.nodes * 4 < default.nodes,
+        "{} {}",
+        none.nodes,
+        default.nodes
+    );
+}
+
+#[test]
+fn multi_pv_lines_are_distinct_and_share_the_tt() {
+    let mut single = TestSearch::new(QUIET, 6);
+    let single_result = single.run();
+
+    let mut multi = TestSearch::new(QUIET, 6);
+    multi.params.multi_pv = 3;
+    let multi_result = multi.run();
+
+    // The last depth reports three lines, each with its own first move.
+    let last_depth: Vec<SearchSummary> = multi
+        .summaries()
+        .into_iter()
+        .filter(|s| s.depth == multi_result.depth)
+        .collect();
+    let mut first_moves: Vec<u32> = last_depth.iter().map(|s| s.pv[0].get_move()).collect();
+    assert_eq!(last_depth.len(), 3);
+    first_moves.sort_unstable();
+    first_moves.dedup();
+    assert_eq!(first_moves.len(), 3);
+
+    // The lines after the first one are searched with the TT the earlier
+    // lines filled, so three lines cost well under three searches.
+    assert!(multi_result.nodes * 2 < single_result.nodes * 5);
+}
+
+#[test]
+fn tt_reductions_save_nodes() {
+    const DEPTH: i8 = 8;
+    let mut reduced = TestSearch::new(QUIET, DEPTH);
+    let with_reductions = reduced.run();
+
+    let mut full = TestSearch::new(QUIET, DEPTH);
+    full.params.tt_reductions = false;
+    let without_reductions = full.run();
+
+    assert!(with_reductions.nodes < without_reductions.nodes);
+    assert_eq!(
+        with_reductions.best_move.as_string(),
+        without_reductions.best_move.as_string()
+    );
+}
+
+// The tactics that the -a option of the extra module runs, with the move
+// to play in SAN. At this depth, the search finds all of them.
+const EASY_TACTICS: [(&str, &str); 5] = [
+    (
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1",
+        "Qg6",
+    ),
+    (
+        "r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - 0 1",
+        "Qxh7+",
+    ),
+    ("5k2/6pp/p1qN4/1p1p4/3P4/2PKP2Q/PP3r2/3R4 b - - 0 1", "Qc4+"),
+    ("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", "Ra8#"),
+    ("8/2q1P1k1/8/8/8/8/8/K7 w - - 0 1", "e8=N+"),
+];
+const EASY_TACTICS_DEPTH: i8 = 6;
+
+#[test]
+fn easy_tactics_are_solved() {
+    for (fen, san) in EASY_TACTICS {
+        let mut search = TestSearch::new(fen, EASY_TACTICS_DEPTH);
+        let best = search.board.san_to_move(san, &search.mg);
+        let result = search.run();
+        assert!(
+            best.is_some_and(|m| m.get_move() == result.best_move.get_move()),
+            "{fen} {san}"
+        );
+    }
+
+    // With the king in the corner, Qb6 is stalemate.
+    let mut search = TestSearch::new("k7/8/2K5/1Q6/8/8/8/8 w - - 0 1", EASY_TACTICS_DEPTH);
+    let stalemate = search.board.san_to_move("Qb6", &search.mg);
+    let result = search.run();
+    assert!(stalemate.is_some_and(|m| m.get_move() != result.best_move.get_move()));
+}
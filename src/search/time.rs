@@ -29,24 +29,58 @@ const GAME_LENGTH: usize = 25; // moves
 const MOVES_BUFFER: usize = 5; //moves
 const CRITICAL_TIME: u128 = 1_000; // msecs
 const OK_TIME: u128 = CRITICAL_TIME * 5; // msecs
+const BEST_MOVE_CHANGE_DECAY: f64 = 0.5; // Weight of older changes, per iteration
+const BEST_MOVE_CHANGE_EXTENSION: f64 = 0.3; // Extra time per (weighted) change
 
 impl Search {
     // Determine if allocated search time has been used up.
     pub fn out_of_time(refs: &mut SearchRefs) -> bool {
         let elapsed = refs.search_info.timer_elapsed();
         let allocated = refs.search_info.allocated_time;
+        let overshoot_factor = Search::overshoot_factor(allocated);
 
-        // Calculate a factor with which it is allowed to overshoot the
-        // allocated search time. The more time the engine has, the larger
-        // the overshoot-factor can be.
-        let overshoot_factor = match allocated {
+        elapsed >= (overshoot_factor * allocated as f64).round() as u128
+    }
+
+    // Determine if there's no time left to start another iteration.
+    pub fn soft_time_up(refs: &SearchRefs, best_move_changes: f64) -> bool {
+        let elapsed = refs.search_info.timer_elapsed();
+        let allocated = refs.search_info.allocated_time;
+
+        elapsed > Search::soft_time_limit(allocated, best_move_changes)
+    }
+
+    // While the best move keeps changing, the position is critical, and
+    // the allocated time is extended. The extension never goes beyond the
+    // time out_of_time() allows.
+    fn soft_time_limit(allocated: u128, best_move_changes: f64) -> u128 {
+        let extension = 1.0 + BEST_MOVE_CHANGE_EXTENSION * best_move_changes;
+        let factor = extension.min(Search::overshoot_factor(allocated));
+
+        (factor * allocated as f64).round() as u128
+    }
+
+    // Keeps a weighted count of how often the best move changed: older
+    // changes count less with every iteration.
+    pub fn update_best_move_changes(best_move_changes: f64, changed: bool) -> f64 {
+        let decayed = best_move_changes * BEST_MOVE_CHANGE_DECAY;
+        if changed {
+            decayed + 1.0
+        } else {
+            decayed
+        }
+    }
+
+    // Calculate a factor with which it is allowed to overshoot the
+    // allocated search time. The more time the engine has, the larger
+    // the overshoot-factor can be.
+    fn overshoot_factor(allocated: u128) -> f64 {
+        match allocated {
             x if x > OK_TIME => 2.0,                       // Allow large overshoot.
             x if x > CRITICAL_TIME && x <= OK_TIME => 1.5, // Low on time. Reduce overshoot.
             x if x <= CRITICAL_TIME => 1.0,                // Critical time. Don't overshoot.
             _ => 1.0,                                      // This case shouldn't happen.
-        };
-
-        elapsed >= (overshoot_factor * allocated as f64).round() as u128
+        }
     }
 
     // Calculates the time the engine allocates for searching a single
@@ -91,3 +125,33 @@ impl Search {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_time_limit_grows_while_the_best_move_changes() {
+        const ALLOCATED: u128 = 2_000;
+        let flipped = Search::update_best_move_changes(0.0, true);
+        let settled = Search::update_best_move_changes(flipped, false);
+
+        // Without changes, the allocated time is the limit.
+        assert_eq!(Search::soft_time_limit(ALLOCATED, 0.0), ALLOCATED);
+
+        // A flip of the best move extends the limit, and less so when it
+        // was an iteration ago.
+        let after_flip = Search::soft_time_limit(ALLOCATED, flipped);
+        let after_settling = Search::soft_time_limit(ALLOCATED, settled);
+        assert!(after_flip > after_settling && after_settling > ALLOCATED);
+
+        // The extension stops at the hard limit, and is not given when
+        // time is critical.
+        let hard_limit = (Search::overshoot_factor(ALLOCATED) * ALLOCATED as f64) as u128;
+        assert_eq!(Search::soft_time_limit(ALLOCATED, 100.0), hard_limit);
+        assert_eq!(
+            Search::soft_time_limit(CRITICAL_TIME, flipped),
+            CRITICAL_TIME
+        );
+    }
+}
@@ -23,16 +23,18 @@ with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
     defs::{
-        SearchControl, SearchCurrentMove, SearchMode, SearchRefs, SearchReport, SearchStats,
-        SearchTerminate, MAX_KILLER_MOVES, MIN_TIME_CURR_MOVE, MIN_TIME_STATS,
+        NodeReturn, SearchControl, SearchCurrentMove, SearchMode, SearchRefs, SearchReport,
+        SearchStats, SearchTerminate, CHECKMATE, CHECKMATE_THRESHOLD, CHECKPOINT_GROWTH,
+        CHECKPOINT_MAX_INTERVAL, CHECKPOINT_MIN_INTERVAL, FORTRESS_MIN_ITERATIONS,
+        FORTRESS_MIN_PLIES, MAX_HISTORY, MAX_KILLER_MOVES, MIN_TIME_CURR_MOVE, MIN_TIME_STATS,
     },
     Search,
 };
 use crate::{
     board::{defs::Pieces, Board},
-    defs::{Sides, MAX_MOVE_RULE},
-    engine::defs::{ErrFatal, Information},
-    movegen::defs::Move,
+    defs::{Side, MAX_MOVE_RULE, MAX_PLY},
+    engine::defs::{AnalysisData, ErrFatal, HashFlag, Information},
+    movegen::defs::{Move, MoveList, ShortMove},
 };
 
 impl Search {
@@ -46,6 +48,59 @@ impl Search {
         nps
     }
 
+    // Converts a score into the number of moves (not plies) to mate. The
+    // result is negative if the side to move is being mated. If the score
+    // is not a mate score, 0 is returned.
+    pub fn moves_to_mate(score: i16) -> i16 {
+        if score.abs() >= CHECKMATE_THRESHOLD && score.abs() < CHECKMATE {
+            // Number of plies to mate.
+            let ply = CHECKMATE - score.abs();
+
+            // An odd number of plies ends with our own move, so round up.
+            let moves = (ply + 1) / 2;
+
+            // If the engine is being mated itself, flip the score.
+            if score < 0 {
+                -moves
+            } else {
+                moves
+            }
+        } else {
+            0
+        }
+    }
+
+    // Heuristic for positions where the side that seems to be winning
+    // can't make progress, such as a fortress. If the score has not
+    // changed for a number of iterations, and the game plus the PV have
+    // gone on for a long time without captures or pawn moves, then the
+    // score is scaled toward a draw. The scaling starts gently at
+    // FORTRESS_MIN_PLIES, and grows linearly until the score is a draw
+    // when the 50-move rule is reached. Mate scores are never scaled.
+    pub fn fortress_score(score: i16, stable_iterations: u8, pv: &[Move], board: &Board) -> i16 {
+        let is_mate = Search::moves_to_mate(score) != 0;
+        if is_mate || stable_iterations < FORTRESS_MIN_ITERATIONS {
+            return score;
+        }
+
+        // Any capture or pawn move in the PV means there is progress.
+        let pv_progress = pv
+            .iter()
+            .any(|m| m.captured() != Pieces::NONE || m.piece() == Pieces::PAWN);
+        if pv_progress {
+            return score;
+        }
+
+        let no_progress = board.game_state.halfmove_clock as usize + pv.len();
+        if no_progress < FORTRESS_MIN_PLIES as usize {
+            return score;
+        }
+
+        let remaining = MAX_MOVE_RULE as usize - no_progress.min(MAX_MOVE_RULE as usize);
+        let ramp = (MAX_MOVE_RULE - FORTRESS_MIN_PLIES) as i32;
+        ((score as i32 * remaining as i32) / ramp) as i16
+    }
+
     // Send intermediate statistics to GUI.
     pub fn send_stats_to_gui(refs: &mut SearchRefs) {
         let elapsed = refs.search_info.timer_elapsed();
@@ -55,7 +110,8 @@ impl Search {
             let hash_full = refs.tt.lock().expect(ErrFatal::LOCK).hash_full();
             let msecs = refs.search_info.timer_elapsed();
             let nps = Search::nodes_per_second(refs.search_info.nodes, msecs);
-            let stats = SearchStats::new(msecs, refs.search_info.nodes, nps, hash_full);
+            let tb_hits = refs.search_info.tb_hits;
+            let stats = SearchStats::new(msecs, refs.search_info.nodes, nps, tb_hits, hash_full);
             let stats_report = SearchReport::SearchStats(stats);
             let information = Information::Search(stats_report);
 
@@ -79,6 +135,49 @@ impl Search {
         }
     }
 
+    // Returns true if the search has reached the next checkpoint, where
+    // termination conditions should be checked. The interval between
+    // checkpoints starts small so "stop" is handled quickly in short
+    // searches, and grows with the number of nodes searched so long
+    // searches spend less time checking. In node-limited mode, the
+    // checkpoint never skips past the node limit.
+    pub fn is_checkpoint(refs: &mut SearchRefs) -> bool {
+        let nodes = refs.search_info.nodes;
+        if nodes < refs.search_info.next_checkpoint {
+            return false;
+        }
+
+        let interval =
+            (nodes / CHECKPOINT_GROWTH).clamp(CHECKPOINT_MIN_INTERVAL, CHECKPOINT_MAX_INTERVAL);
+        let mut next = nodes + interval;
+        if refs.search_params.search_mode == SearchMode::Nodes && nodes < refs.search_params.nodes {
+            next = next.min(refs.search_params.nodes);
+        }
+        refs.search_info.next_checkpoint = next;
+
+        true
+    }
+
+    // Prints the reason why a node returned to STDERR, indented by ply,
+    // so the UCI output on STDOUT isn't disturbed. Only called when the
+    // engine is compiled with the "trace" feature.
+    pub fn trace_node(
+        depth: i8,
+        alpha: i16,
+        beta: i16,
+        score: i16,
+        reason: NodeReturn,
+        refs: &SearchRefs,
+    ) {
+        let ply = refs.search_info.ply as usize;
+        eprintln!(
+            "{:indent$}ply {ply} depth {depth} key {:016x} window [{alpha}, {beta}] score {score} {reason:?}",
+            "",
+            refs.board.game_state.zobrist_key,
+            indent = ply * 2,
+        );
+    }
+
     // This function checks termination conditions and sets the termination
     // flag if this is required.
     pub fn check_termination(refs: &mut SearchRefs) {
@@ -93,7 +192,12 @@ impl Search {
         // Terminate search if certain conditions are met.
         let search_mode = refs.search_params.search_mode;
         match search_mode {
-            SearchMode::Depth => {
+            // These modes don't look at the clock or the node count, so
+            // the search is deterministic: the same position, depth and
+            // parameters give the same node count and best move. (Only
+            // the transposition table carries over from earlier searches;
+            // 'ucinewgame' clears it.)
+            SearchMode::Depth | SearchMode::Mate => {
                 if refs.search_info.depth > refs.search_params.depth {
                     refs.search_info.terminate = SearchTerminate::Stop
                 }
@@ -119,12 +223,54 @@ impl Search {
         }
     }
 
-    // Returns true if the position should be evaluated as a draw.
-    pub fn is_draw(refs: &SearchRefs) -> bool {
-        let is_max_move_rule = refs.board.game_state.halfmove_clock >= MAX_MOVE_RULE;
-        Search::is_insufficient_material(refs)
-            || Search::is_repetition(refs.board) > 0
-            || is_max_move_rule
+    // Returns true if the static evaluation of the side to move is better
+    // than it was two plies ago, when that side was last to move. If it
+    // was in check then, the evaluation of four plies ago is used. If
+    // there is nothing to compare with, the position is assumed to be
+    // improving.
+    pub fn is_improving(refs: &SearchRefs) -> bool {
+        let ply = refs.search_info.ply as usize;
+        let evals = &refs.search_info.static_evals;
+        let previous = match ply {
+            0 | 1 => None,
+            2 | 3 => evals[ply - 2],
+            _ => evals[ply - 2].or(evals[ply - 4]),
+        };
+
+        match (evals[ply], previous) {
+            (Some(now), Some(before)) => now > before,
+            (None, _) => false,
+            (Some(_), None) => true,
+        }
+    }
+
+    // In analysis mode, annotate the current position with the result of
+    // its search. The number of nodes below it is the difference between
+    // the node count now and when the position was entered.
+    pub fn annotate(
+        depth: i8,
+        flag: HashFlag,
+        value: i16,
+        best_move: ShortMove,
+        static_eval: Option<i16>,
+        nodes_at_entry: usize,
+        refs: &mut SearchRefs,
+    ) {
+        let nodes = refs.search_info.nodes - nodes_at_entry;
+        let zobrist_key = refs.board.game_state.zobrist_key;
+
+        if let Some(analysis) = refs.analysis.as_deref_mut() {
+            let data = AnalysisData::create(depth, flag, value, best_move, static_eval, nodes);
+            analysis.insert(zobrist_key, data);
+        }
+    }
+
+    // Returns true if the position should be evaluated as a draw. The
+    // cheap checks for material and the 50-move rule are done first, so
+    // the repetition scan (which walks the history) is only done if they
+    // didn't already find a draw.
+    pub fn is_draw(refs: &mut SearchRefs) -> bool {
+        refs.board.is_draw_by_material_and_rule(refs.mg) || Search::is_repetition(refs.board) > 0
     }
 
     // Detects position repetitions in the game's history.
@@ -157,37 +303,6 @@ impl Search {
     }
 }
 
-// This is in its own block so rustfmt::skip can be applied. Otherwhise
-// the layout of this function becomes very messy.
-#[rustfmt::skip]
-impl Search {
-    pub fn is_insufficient_material(refs: &SearchRefs) -> bool {
-        // It's not a draw if: ...there are still pawns.
-        let w_p = refs.board.get_pieces(Pieces::PAWN, Sides::WHITE).count_ones() > 0;     
-        let b_p = refs.board.get_pieces(Pieces::PAWN, Sides::BLACK).count_ones() > 0;        
-        // ...there's a major piece on the board.
-        let w_q = refs.board.get_pieces(Pieces::QUEEN, Sides::WHITE).count_ones() > 0;
-        let b_q = refs.board.get_pieces(Pieces::QUEEN, Sides::BLACK).count_ones() > 0;
-        let w_r = refs.board.get_pieces(Pieces::ROOK, Sides::WHITE).count_ones() > 0;
-        let b_r = refs.board.get_pieces(Pieces::ROOK, Sides::BLACK).count_ones() > 0;
-        // ...or two bishops for one side.
-        // FIXME : Bishops must be on squares of different color
-        let w_b = refs.board.get_pieces(Pieces::BISHOP, Sides::WHITE).count_ones() > 1;
-        let b_b = refs.board.get_pieces(Pieces::BISHOP, Sides::BLACK).count_ones() > 1;
-        // ... or a bishop+knight for at least one side.
-        let w_bn =
-            refs.board.get_pieces(Pieces::BISHOP, Sides::WHITE).count_ones() > 0 &&
-            refs.board.get_pieces(Pieces::KNIGHT, Sides::WHITE).count_ones() > 0;
-        let b_bn =
-            refs.board.get_pieces(Pieces::BISHOP, Sides::BLACK).count_ones() > 0 &&
-            refs.board.get_pieces(Pieces::KNIGHT, Sides::BLACK).count_ones() > 0;
-         
-        // If one of the conditions above is true, we still have enough
-        // material for checkmate, so insufficient_material returns false.
-        !(w_p || b_p || w_q || b_q || w_r || b_r || w_b || b_b ||  w_bn || b_bn)
-    }
-}
-
 // Killer moves and history heuristics.
 impl Search {
     // This function stores a move in the list of killer moves. Normally we
@@ -214,4 +329,263 @@ impl Search {
             refs.search_info.killer_moves[ply][0] = current_move.to_short_move();
         }
     }
+
+    // Clears the killer moves at the given ply, if it exists.
+    pub fn clear_killer_moves(ply: i8, refs: &mut SearchRefs) {
+        if ply < MAX_PLY {
+            refs.search_info.killer_moves[ply as usize] = [ShortMove::new(0); MAX_KILLER_MOVES];
+        }
+    }
+
+    // This function updates the history heuristic after a quiet move caused
+    // a beta cutoff. That move gets a bonus; the quiet moves that were
+    // searched before it in the same node, but didn't cause a cutoff, get
+    // a penalty of the same size. Moves that keep failing end up with a
+    // negative score. The bonus grows with the depth, because a cutoff at
+    // a higher depth is worth more. The update shrinks as the score gets
+    // closer to MAX_HISTORY, so scores can never exceed it.
+    pub fn update_history_heuristic(
+        cutoff_move: Move,
+        quiets_tried: &MoveList,
+        depth: i8,
+        refs: &mut SearchRefs,
+    ) {
+        let bonus = (depth as i32 * depth as i32).min(MAX_HISTORY);
+        let us = refs.board.us();
+
+        Search::update_history(us, cutoff_move, bonus, refs);
+        for i in 0..quiets_tried.len() {
+            Search::update_history(us, quiets_tried.get_move(i), -bonus, refs);
+        }
+    }
+
+    fn update_history(side: Side, m: Move, bonus: i32, refs: &mut SearchRefs) {
+        let entry = &mut refs.search_info.history_heuristic[side][m.piece()][m.to()];
+        *entry = Search::history_gravity(*entry, bonus);
+    }
+
+    // Applies a bonus (or a penalty, if negative) to a history score. The
+    // closer the score already is to MAX_HISTORY in the direction of the
+    // bonus, the less of the bonus is added.
+    fn history_gravity(score: i32, bonus: i32) -> i32 {
+        score + bonus - score * bonus.abs() / MAX_HISTORY
+    }
+
+    // Returns the history heuristic score of the given quiet move.
+    pub fn history_score(m: Move, refs: &SearchRefs) -> i32 {
+        refs.search_info.history_heuristic[refs.board.us()][m.piece()][m.to()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{movegen::MoveGenerator, search::tests::TestSearch};
+
+    // White can't win with a rook pawn and a bishop that doesn't control
+    // the promotion square, as long as the black king holds the corner.
+    const FORTRESS: &str = "7k/8/8/7P/8/8/8/K4B2 w - -";
+
+    // Sets up the fortress with the given halfmove clock, and returns the
+    // board together with a PV of two king moves.
+    fn fortress(halfmove_clock: u8) -> (Board, Vec<Move>) {
+        let mg = MoveGenerator::new();
+        let mut board = Board::new();
+        let fen = format!("{FORTRESS} {halfmove_clock} 80");
+        assert!(board.fen_read(Some(&fen)).is_ok());
+
+        let mut pv = Vec::new();
+        let mut position = board.clone();
+        for _ in 0..2 {
+            let moves = position.legal_moves(&mg);
+            let king_move = (0..moves.len())
+                .map(|i| moves.get_move(i))
+                .find(|m| m.piece() == Pieces::KING)
+                .unwrap();
+            assert!(position.make(king_move, &mg));
+            pv.push(king_move);
+        }
+
+        (board, pv)
+    }
+
+    #[test]
+    fn moves_to_mate_rounds_plies_up() {
+        assert_eq!(Search::moves_to_mate(CHECKMATE - 1), 1);
+        assert_eq!(Search::moves_to_mate(CHECKMATE - 5), 3);
+        assert_eq!(Search::moves_to_mate(CHECKMATE - 6), 3);
+        assert_eq!(Search::moves_to_mate(-(CHECKMATE - 2)), -1);
+        assert_eq!(Search::moves_to_mate(-(CHECKMATE - 4)), -2);
+        assert_eq!(Search::moves_to_mate(0), 0);
+        assert_eq!(Search::moves_to_mate(CHECKMATE_THRESHOLD - 1), 0);
+        assert_eq!(Search::moves_to_mate(-(CHECKMATE_THRESHOLD - 1)), 0);
+    }
+
+    #[test]
+    fn fortress_score_ramps_in() {
+        let score = 300;
+        let stable = FORTRESS_MIN_ITERATIONS;
+
+        let (board, pv) = fortress(10);
+        assert_eq!(Search::fortress_score(score, stable, &pv, &board), score);
+        let (board, pv) = fortress(FORTRESS_MIN_PLIES - 2);
+        assert_eq!(Search::fortress_score(score, stable, &pv, &board), score);
+        let (board, pv) = fortress(68);
+        assert_eq!(
+            Search::fortress_score(score, stable, &pv, &board),
+            score / 2
+        );
+        let (board, pv) = fortress(MAX_MOVE_RULE - 2);
+        assert_eq!(Search::fortress_score(score, stable, &pv, &board), 0);
+    }
+
+    #[test]
+    fn fortress_score_needs_a_stable_score() {
+        let (board, pv) = fortress(68);
+        let unstable = FORTRESS_MIN_ITERATIONS - 1;
+        assert_eq!(Search::fortress_score(300, unstable, &pv, &board), 300);
+    }
+
+    #[test]
+    fn fortress_score_keeps_mate_scores() {
+        let (board, pv) = fortress(MAX_MOVE_RULE - 2);
+        let stable = FORTRESS_MIN_ITERATIONS;
+        let mate = CHECKMATE - 5;
+        assert_eq!(Search::fortress_score(mate, stable, &pv, &board), mate);
+    }
+
+    #[test]
+    fn repetition_ignores_move_counters() {
+        let position = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -";
+        let mut board = Board::new();
+        let mut other = Board::new();
+        assert!(board.fen_read(Some(&format!("{position} 2 3"))).is_ok());
+        assert!(other.fen_read(Some(&format!("{position} 37 81"))).is_ok());
+        assert_eq!(board.game_state.zobrist_key, other.game_state.zobrist_key);
+
+        // After both bishops develop, both knights go out and back: the
+        // position repeats, with different move counters.
+        let mg = MoveGenerator::new();
+        for uci in ["f1e2", "f8e7"] {
+            assert!(board.make_uci(uci, &mg).is_ok());
+        }
+        let key = board.game_state.zobrist_key;
+        for uci in ["f3g1", "c6b8", "g1f3", "b8c6"] {
+            assert_eq!(Search::is_repetition(&board), 0);
+            assert!(board.make_uci(uci, &mg).is_ok());
+        }
+        assert_eq!(board.game_state.halfmove_clock, 8);
+        assert_eq!(board.game_state.fullmove_number, 6);
+        assert_eq!(board.game_state.zobrist_key, key);
+        assert_eq!(Search::is_repetition(&board), 1);
+    }
+
+    #[test]
+    fn history_gravity_stays_within_max() {
+        let max_bonus = (MAX_PLY as i32 * MAX_PLY as i32).min(MAX_HISTORY);
+        let mut good = 0;
+        let mut bad = 0;
+
+        for _ in 0..1_000 {
+            good = Search::history_gravity(good, max_bonus);
+            bad = Search::history_gravity(bad, -max_bonus);
+            assert!((0..=MAX_HISTORY).contains(&good));
+            assert!((-MAX_HISTORY..=0).contains(&bad));
+        }
+
+        // Both converge toward the limit.
+        assert!(good > MAX_HISTORY * 99 / 100);
+        assert!(bad < -MAX_HISTORY * 99 / 100);
+    }
+
+    #[test]
+    fn history_gravity_keeps_the_order() {
+        // One move causes cutoffs at depth 8, another one at depth 4. A
+        // third one causes cutoffs at depth 8 as well, but half as often.
+        let mut deep = 0;
+        let mut shallow = 0;
+        let mut rare = 0;
+
+        for i in 0..200 {
+            deep = Search::history_gravity(deep, 64);
+            shallow = Search::history_gravity(shallow, 16);
+            let bonus = if i % 2 == 0 { -64 } else { 64 };
+            rare = Search::history_gravity(rare, bonus);
+
+            assert!(deep > rare);
+            assert!(deep >= shallow);
+        }
+
+        // The move that keeps failing half of the time falls behind the
+        // one with the shallow cutoffs.
+        assert!(shallow > rare);
+    }
+
+    #[test]
+    fn killer_moves_shift_down() {
+        const PLY: i8 = 3;
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut search = TestSearch::new(fen, 1);
+
+        search.with_refs(|refs| {
+            let moves = refs.board.legal_moves(refs.mg);
+            let find = |uci: &str| {
+                (0..moves.len())
+                    .map(|i| moves.get_move(i))
+                    .find(|m| m.as_string() == uci)
+                    .unwrap()
+            };
+            let killers = |refs: &SearchRefs| {
+                refs.search_info.killer_moves[PLY as usize].map(|k| k.get_move())
+            };
+            let (e4, d4, nf3) = (find("e2e4"), find("d2d4"), find("g1f3"));
+            refs.search_info.ply = PLY;
+
+            Search::store_killer_move(e4, refs);
+            assert_eq!(killers(refs), [e4.to_short_move().get_move(), 0]);
+
+            // A new cutoff move goes to slot 0, and the old one to slot 1.
+            Search::store_killer_move(d4, refs);
+            let expected = [d4.to_short_move(), e4.to_short_move()].map(|k| k.get_move());
+            assert_eq!(killers(refs), expected);
+
+            // Storing the first killer again changes nothing.
+            Search::store_killer_move(d4, refs);
+            assert_eq!(killers(refs), expected);
+
+            // The old slot 1 move drops out.
+            Search::store_killer_move(nf3, refs);
+            let expected = [nf3.to_short_move(), d4.to_short_move()].map(|k| k.get_move());
+            assert_eq!(killers(refs), expected);
+
+            // Other plies are untouched.
+            let other = refs.search_info.killer_moves[PLY as usize + 1];
+            assert!(other.iter().all(|k| k.get_move() == 0));
+        });
+    }
+
+    #[test]
+    fn improving_compares_with_two_plies_ago() {
+        // Static evaluations per ply, each from the side to move's point of
+        // view. The side to move at ply 3 is in check, so it has none.
+        let trajectory = [Some(10), Some(-5), Some(20), None, Some(15), Some(0)];
+        let expected = [
+            true,  // Nothing to compare with
+            true,  // Nothing to compare with
+            true,  // 20 > 10
+            false, // In check
+            false, // 15 < 20
+            true,  // Ply 3 was in check, so 0 > -5 at ply 1
+        ];
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut search = TestSearch::new(fen, 1);
+
+        search.with_refs(|refs| {
+            for (ply, eval) in trajectory.iter().enumerate() {
+                refs.search_info.ply = ply as i8;
+                refs.search_info.static_evals[ply] = *eval;
+                assert_eq!(Search::is_improving(refs), expected[ply], "ply {ply}");
+            }
+        });
+    }
 }